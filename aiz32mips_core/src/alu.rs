@@ -21,15 +21,37 @@ pub enum AluOp {
     Mtlo,
     Lui,
     Teq,
+    Tne,
+    Tge,
+    Tgeu,
+    Tlt,
+    Tltu,
+    Movz,
+    Movn,
+    Sync,
+    /// Encoding the ALU does not implement: the caller raises RI.
     None,
 }
 
+impl AluOp {
+    pub fn is_trap(self) -> bool {
+        matches!(
+            self,
+            AluOp::Teq | AluOp::Tne | AluOp::Tge | AluOp::Tgeu | AluOp::Tlt | AluOp::Tltu
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct AluResult {
     pub value: u32,
     pub hi: Option<u32>,
     pub lo: Option<u32>,
     pub overflow: bool,
+    /// Trap condition held (TEQ, TNE, TGE, ...).
+    pub trap: bool,
+    /// False when rd must be left untouched (traps, MOVZ/MOVN not taken).
+    pub write_rd: bool,
     pub op: AluOp,
 }
 
@@ -51,7 +73,9 @@ impl ALU {
         hi: u32,
         lo: u32,
     ) -> AluResult {
-        let overflow = false;
+        let mut overflow = false;
+        let mut trap = false;
+        let mut write_rd = true;
         let mut result = 0;
         let mut hi_res = None;
         let mut lo_res = None;
@@ -63,8 +87,10 @@ impl ALU {
             
             match opcode {
                 0x08 => {
-                    // ADDI
-                    result = rs_val.wrapping_add(imm_se);
+                    // ADDI (traps on signed overflow)
+                    let (sum, ov) = (rs_val as i32).overflowing_add(imm_se as i32);
+                    result = sum as u32;
+                    overflow = ov;
                     op = AluOp::Add;
                 }
                 0x09 => {
@@ -106,14 +132,16 @@ impl ALU {
                     result = (imm as u32) << 16;
                     op = AluOp::Lui;
                 }
-                _ => println!("[ALU] Unhandled I-type opcode 0x{:02X}", opcode),
+                _ => {}
             }
         } else {
             // === R-TYPE ===
             match funct {
                 // ---- Aritméticas ----
                 0x20 => {
-                    result = rs_val.wrapping_add(rt_val);
+                    let (sum, ov) = (rs_val as i32).overflowing_add(rt_val as i32);
+                    result = sum as u32;
+                    overflow = ov;
                     op = AluOp::Add;
                 } // ADD
                 0x21 => {
@@ -121,7 +149,9 @@ impl ALU {
                     op = AluOp::Add;
                 } // ADDU
                 0x22 => {
-                    result = rs_val.wrapping_sub(rt_val);
+                    let (diff, ov) = (rs_val as i32).overflowing_sub(rt_val as i32);
+                    result = diff as u32;
+                    overflow = ov;
                     op = AluOp::Sub;
                 } // SUB
                 0x23 => {
@@ -158,13 +188,42 @@ impl ALU {
                     result = if rs_val < rt_val { 1 } else { 0 };
                     op = AluOp::Sltu;
                 }
+                // ---- Traps ----
+                0x30 => {
+                    trap = (rs_val as i32) >= (rt_val as i32);
+                    op = AluOp::Tge;
+                } // TGE
+                0x31 => {
+                    trap = rs_val >= rt_val;
+                    op = AluOp::Tgeu;
+                } // TGEU
+                0x32 => {
+                    trap = (rs_val as i32) < (rt_val as i32);
+                    op = AluOp::Tlt;
+                } // TLT
+                0x33 => {
+                    trap = rs_val < rt_val;
+                    op = AluOp::Tltu;
+                } // TLTU
                 0x34 => {
-                    // TEQ
-                    if rs_val == rt_val {
-                        result = 1;
-                    }
+                    trap = rs_val == rt_val;
                     op = AluOp::Teq;
-                }
+                } // TEQ
+                0x36 => {
+                    trap = rs_val != rt_val;
+                    op = AluOp::Tne;
+                } // TNE
+                // ---- Movimientos condicionales ----
+                0x0A => {
+                    result = rs_val;
+                    write_rd = rt_val == 0;
+                    op = AluOp::Movz;
+                } // MOVZ
+                0x0B => {
+                    result = rs_val;
+                    write_rd = rt_val != 0;
+                    op = AluOp::Movn;
+                } // MOVN
                 // ---- Desplazamientos ----
                 0x00 => {
                     result = rt_val << shamt;
@@ -206,16 +265,26 @@ impl ALU {
                     op = AluOp::Multu;
                 }
                 0x1A => {
-                    // DIV
-                    if rt_val != 0 {
-                        hi_res = Some((rs_val as i32 % rt_val as i32) as u32);
-                        lo_res = Some((rs_val as i32 / rt_val as i32) as u32);
+                    // DIV. The architecture leaves HI/LO UNPREDICTABLE on a
+                    // zero divisor; we return what the R3000A divider
+                    // produces (HI = rs, LO = -1 or +1 against the sign of
+                    // rs). INT_MIN / -1 wraps: LO = INT_MIN, HI = 0.
+                    let (n, d) = (rs_val as i32, rt_val as i32);
+                    if d == 0 {
+                        hi_res = Some(rs_val);
+                        lo_res = Some(if n >= 0 { 0xFFFF_FFFF } else { 1 });
+                    } else {
+                        hi_res = Some(n.wrapping_rem(d) as u32);
+                        lo_res = Some(n.wrapping_div(d) as u32);
                     }
                     op = AluOp::Div;
                 }
                 0x1B => {
-                    // DIVU
-                    if rt_val != 0 {
+                    // DIVU. Zero divisor: HI = rs, LO = 0xFFFFFFFF (R3000A)
+                    if rt_val == 0 {
+                        hi_res = Some(rs_val);
+                        lo_res = Some(0xFFFF_FFFF);
+                    } else {
                         hi_res = Some(rs_val % rt_val);
                         lo_res = Some(rs_val / rt_val);
                     }
@@ -237,20 +306,82 @@ impl ALU {
                     lo_res = Some(rs_val);
                     op = AluOp::Mtlo;
                 }
-                0x08 |
-                0x09 |
-                0x0C |
-                0x0D => {}
-                _ => println!("[ALU] Unhandled R-type funct 0x{:02X}", funct),
+                0x0F => {
+                    // SYNC: nothing to order here
+                    op = AluOp::Sync;
+                }
+                // JR / JALR run in the CPU
+                _ => {}
             }
         }
 
+        if op.is_trap() || op == AluOp::None {
+            write_rd = false;
+        }
+
         AluResult {
             value: result,
             hi: hi_res,
             lo: lo_res,
             overflow,
+            trap,
+            write_rd,
             op,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs a SPECIAL divide and returns (HI, LO).
+    fn div(funct: u8, rs: u32, rt: u32) -> (u32, u32) {
+        let res = ALU::execute(rs, rt, 0, 0, 0, funct, false, 0xDEAD, 0xBEEF);
+        (res.hi.unwrap(), res.lo.unwrap())
+    }
+
+    #[test]
+    fn div_signed() {
+        assert_eq!(div(0x1A, 7, 2), (1, 3));
+        assert_eq!(div(0x1A, -7i32 as u32, 2), (-1i32 as u32, -3i32 as u32));
+        assert_eq!(div(0x1A, 7, -2i32 as u32), (1, -3i32 as u32));
+    }
+
+    #[test]
+    fn div_by_zero() {
+        assert_eq!(div(0x1A, 5, 0), (5, 0xFFFF_FFFF));
+        assert_eq!(div(0x1A, 0, 0), (0, 0xFFFF_FFFF));
+        assert_eq!(div(0x1A, -5i32 as u32, 0), (-5i32 as u32, 1));
+        assert_eq!(div(0x1B, 5, 0), (5, 0xFFFF_FFFF));
+        assert_eq!(div(0x1B, 0x8000_0000, 0), (0x8000_0000, 0xFFFF_FFFF));
+    }
+
+    #[test]
+    fn div_int_min_by_minus_one() {
+        assert_eq!(div(0x1A, 0x8000_0000, 0xFFFF_FFFF), (0, 0x8000_0000));
+    }
+
+    #[test]
+    fn divu() {
+        assert_eq!(div(0x1B, 0xFFFF_FFFF, 2), (1, 0x7FFF_FFFF));
+        assert_eq!(div(0x1B, 0x8000_0000, 0xFFFF_FFFF), (0x8000_0000, 0));
+    }
+
+    #[test]
+    fn undefined_encodings_map_to_none() {
+        let defined = [
+            0x00u8, 0x02, 0x03, 0x04, 0x06, 0x07, 0x0A, 0x0B, 0x0F, 0x10, 0x11, 0x12, 0x13, 0x18,
+            0x19, 0x1A, 0x1B, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x2A, 0x2B, 0x30,
+            0x31, 0x32, 0x33, 0x34, 0x36,
+        ];
+        for funct in 0u8..0x40 {
+            let op = ALU::execute(1, 2, 0, 0, 0, funct, false, 0, 0).op;
+            assert_eq!(op != AluOp::None, defined.contains(&funct), "funct {:02X}", funct);
+        }
+        for opcode in 0x08u8..=0x0F {
+            let op = ALU::execute(1, 2, 0, 3, opcode, 0, true, 0, 0).op;
+            assert_ne!(op, AluOp::None, "opcode {:02X}", opcode);
+        }
+    }
+}
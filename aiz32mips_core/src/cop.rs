@@ -1,25 +1,271 @@
+use crate::exception::{Exception, ExceptionCode};
+use crate::fpu::FPE_UNIMPLEMENTED;
+use crate::model::{CpuModel, IsaRevision};
+use crate::tlb::{
+    ENTRYHI_ASID_MASK, ENTRYHI_VPN2_MASK, ENTRYLO_WRITE_MASK, INDEX_P, PAGEMASK_MASK,
+};
+
+// Status bits
+pub const STATUS_IE: u32 = 1 << 0;
+pub const STATUS_EXL: u32 = 1 << 1;
+pub const STATUS_ERL: u32 = 1 << 2;
+pub const STATUS_IM_MASK: u32 = 0xFF << 8;
+pub const STATUS_KSU_SHIFT: u32 = 3;
+pub const STATUS_KSU_MASK: u32 = 0x3 << STATUS_KSU_SHIFT;
+pub const STATUS_BEV: u32 = 1 << 22;
+pub const STATUS_CU0: u32 = 1 << 28;
+pub const STATUS_CU1: u32 = 1 << 29;
+pub const STATUS_FR: u32 = 1 << 26;
+/// Bits software may change with MTC0 (CU, RP, FR, RE, BEV, SR, NMI, IM,
+/// KSU, ERL, EXL, IE).
+pub const STATUS_WRITE_MASK: u32 = 0xFE58_FF1F;
+
+// Cause bits
+pub const CAUSE_EXCCODE_SHIFT: u32 = 2;
+pub const CAUSE_EXCCODE_MASK: u32 = 0x1F << CAUSE_EXCCODE_SHIFT;
+pub const CAUSE_IP_MASK: u32 = 0xFF << 8;
+pub const CAUSE_IP2: u32 = 1 << 10;
+pub const CAUSE_IP7: u32 = 1 << 15;
+pub const CAUSE_IV: u32 = 1 << 23;
+pub const CAUSE_CE_SHIFT: u32 = 28;
+pub const CAUSE_CE_MASK: u32 = 0x3 << CAUSE_CE_SHIFT;
+pub const CAUSE_TI: u32 = 1 << 30;
+pub const CAUSE_BD: u32 = 1 << 31;
+/// Only DC, IV and the software interrupts IP1..IP0 are writable.
+pub const CAUSE_WRITE_MASK: u32 = 0x0880_0300;
+
+// Context: BadVPN2 holds VA[31:13]
+pub const CONTEXT_BADVPN2_MASK: u32 = 0x007F_FFF0;
+pub const CONTEXT_PTEBASE_MASK: u32 = 0xFF80_0000;
+
+// Debug (EJTAG)
+pub const DEBUG_DBP: u32 = 1 << 1;
+pub const DEBUG_SST: u32 = 1 << 8;
+pub const DEBUG_LSNM: u32 = 1 << 28;
+pub const DEBUG_DM: u32 = 1 << 30;
+pub const DEBUG_DBD: u32 = 1 << 31;
+pub const DEBUG_WRITE_MASK: u32 = DEBUG_LSNM | DEBUG_SST;
+/// Debug exception vector with EJTAG probe disabled.
+pub const DEBUG_VECTOR: u32 = 0xBFC0_0480;
+
+// EBase: exception base (bits 29:12 writable, 31:30 fixed at 0b10)
+pub const EBASE_DEFAULT: u32 = 0x8000_0000;
+pub const EBASE_WRITE_MASK: u32 = 0x3FFF_F000;
+
+// Config registers
+pub const CONFIG_M: u32 = 1 << 31;
+pub const CONFIG0_BE: u32 = 1 << 15;
+pub const CONFIG0_MT_TLB: u32 = 1 << 7;
+pub const CONFIG0_K0_MASK: u32 = 0x7;
+pub const CONFIG1_MMU_SHIFT: u32 = 25;
+pub const CONFIG1_FP: u32 = 1 << 0;
+pub const CONFIG3_ULRI: u32 = 1 << 13;
+
+// HWREna: user-mode RDHWR access to CPUNum, SYNCI_Step, CC, CCRes, UserLocal
+pub const HWRENA_WRITE_MASK: u32 = 0x2000_000F;
+
+// FCSR fields
+pub const FCSR_RM_MASK: u32 = 0x3;
+pub const FCSR_CC0: u32 = 1 << 23;
+pub const FCSR_FS: u32 = 1 << 24;
+pub const FCSR_WRITE_MASK: u32 = 0xFF83_FFFF;
+
+// FIR: single, double and word formats implemented
+pub const FIR_DEFAULT: u32 = (1 << 16) | (1 << 17) | (1 << 20);
+pub const FIR_L: u32 = 1 << 21;
+pub const FIR_F64: u32 = 1 << 22;
+
+/// Processor privilege level, derived from Status.KSU/EXL/ERL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrivilegeMode {
+    Kernel,
+    Supervisor,
+    User,
+}
+
+/// Cop0 Status (register 12).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Status(pub u32);
+
+impl Status {
+    #[inline]
+    pub fn has(self, bits: u32) -> bool {
+        self.0 & bits != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, bits: u32, on: bool) {
+        if on {
+            self.0 |= bits;
+        } else {
+            self.0 &= !bits;
+        }
+    }
+
+    #[inline]
+    pub fn ie(self) -> bool {
+        self.has(STATUS_IE)
+    }
+
+    #[inline]
+    pub fn exl(self) -> bool {
+        self.has(STATUS_EXL)
+    }
+
+    #[inline]
+    pub fn erl(self) -> bool {
+        self.has(STATUS_ERL)
+    }
+
+    #[inline]
+    pub fn bev(self) -> bool {
+        self.has(STATUS_BEV)
+    }
+
+    #[inline]
+    pub fn fr(self) -> bool {
+        self.has(STATUS_FR)
+    }
+
+    /// Coprocessor `unit` (0-3) usable bit.
+    #[inline]
+    pub fn cu(self, unit: u8) -> bool {
+        self.has(STATUS_CU0 << unit)
+    }
+
+    #[inline]
+    pub fn ksu(self) -> u32 {
+        (self.0 & STATUS_KSU_MASK) >> STATUS_KSU_SHIFT
+    }
+
+    /// Interrupt mask, aligned with Cause.IP.
+    #[inline]
+    pub fn im(self) -> u32 {
+        self.0 & STATUS_IM_MASK
+    }
+}
+
+/// Cop0 Cause (register 13).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cause(pub u32);
+
+impl Cause {
+    #[inline]
+    pub fn has(self, bits: u32) -> bool {
+        self.0 & bits != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, bits: u32, on: bool) {
+        if on {
+            self.0 |= bits;
+        } else {
+            self.0 &= !bits;
+        }
+    }
+
+    #[inline]
+    pub fn exc_code(self) -> u32 {
+        (self.0 & CAUSE_EXCCODE_MASK) >> CAUSE_EXCCODE_SHIFT
+    }
+
+    #[inline]
+    pub fn set_exc_code(&mut self, code: u32) {
+        self.0 =
+            (self.0 & !CAUSE_EXCCODE_MASK) | ((code << CAUSE_EXCCODE_SHIFT) & CAUSE_EXCCODE_MASK);
+    }
+
+    #[inline]
+    pub fn ce(self) -> u8 {
+        ((self.0 & CAUSE_CE_MASK) >> CAUSE_CE_SHIFT) as u8
+    }
+
+    #[inline]
+    pub fn set_ce(&mut self, unit: u8) {
+        self.0 = (self.0 & !CAUSE_CE_MASK) | (((unit as u32) << CAUSE_CE_SHIFT) & CAUSE_CE_MASK);
+    }
+
+    /// Pending interrupts, aligned with Status.IM.
+    #[inline]
+    pub fn ip(self) -> u32 {
+        self.0 & CAUSE_IP_MASK
+    }
+
+    #[inline]
+    pub fn bd(self) -> bool {
+        self.has(CAUSE_BD)
+    }
+
+    #[inline]
+    pub fn ti(self) -> bool {
+        self.has(CAUSE_TI)
+    }
+
+    #[inline]
+    pub fn iv(self) -> bool {
+        self.has(CAUSE_IV)
+    }
+}
+
+/// System control coprocessor. Registers without a model here (watch,
+/// performance counters, caches) read as zero and ignore writes.
+#[derive(Clone, Debug)]
 pub struct Cop0 {
-    pub regs: [u32; 32],
+    pub index: u32,
+    pub random: u32,
+    pub entry_lo0: u32,
+    pub entry_lo1: u32,
+    pub context: u32,
+    /// UserLocal (register 4, select 2): TLS pointer read by RDHWR $29.
+    pub user_local: u32,
+    pub page_mask: u32,
+    pub wired: u32,
+    pub hwrena: u32,
+    pub badvaddr: u32,
+    pub count: u32,
+    pub entry_hi: u32,
+    pub compare: u32,
+    pub status: Status,
+    pub cause: Cause,
+    pub epc: u32,
+    pub prid: u32,
+    pub ebase: u32,
+    /// Config0-3 (register 16, selects 0-3).
+    pub config: [u32; 4],
+    /// Physical address of the last LL, shifted right by 4.
+    pub lladdr: u32,
+    pub debug: u32,
+    pub depc: u32,
+    pub error_epc: u32,
+    /// Release 1 cores lack EBase, HWREna and UserLocal.
+    isa: IsaRevision,
+    /// Writable Status bits: CU1 needs an FPU, FR a 64-bit one (FIR.F64).
+    status_write_mask: u32,
 }
 
 pub struct Cop1 {
-    pub f: [f64; 32],
+    /// Raw register contents. Singles and words occupy the low 32 bits;
+    /// use the typed views rather than touching this directly.
+    pub f: [u64; 32],
     pub fcsr: u32,
     pub fir: u32,
+    /// Status.FR: when clear, doubles live in even/odd register pairs.
+    pub fr: bool,
 }
 
 impl Default for Cop0 {
     fn default() -> Self {
-        Cop0 { regs: [0; 32] }
+        Self::for_model(&CpuModel::default())
     }
 }
 
 impl Default for Cop1 {
     fn default() -> Self {
         Cop1 {
-            f: [0.0; 32],
+            f: [0; 32],
             fcsr: 0,
-            fir: 0,
+            fir: FIR_DEFAULT,
+            fr: false,
         }
     }
 }
@@ -29,63 +275,227 @@ impl Cop0 {
         Self::default()
     }
 
-    #[inline]
-    pub fn read(&self, index: usize) -> u32 {
-        self.regs[index]
+    /// Reset state for `model`: bootstrap vectors, error level set, Random
+    /// at the top, PRId/Config describing the core.
+    pub fn for_model(model: &CpuModel) -> Self {
+        Cop0 {
+            index: 0,
+            random: model.tlb_entries() - 1,
+            entry_lo0: 0,
+            entry_lo1: 0,
+            context: 0,
+            user_local: 0,
+            page_mask: 0,
+            wired: 0,
+            hwrena: 0,
+            badvaddr: 0,
+            count: 0,
+            entry_hi: 0,
+            compare: 0,
+            status: Status(STATUS_BEV | STATUS_ERL),
+            cause: Cause(0),
+            epc: 0,
+            prid: model.prid,
+            ebase: EBASE_DEFAULT,
+            config: [
+                model.config0(),
+                model.config1(),
+                model.config2(),
+                model.config3(),
+            ],
+            lladdr: 0,
+            debug: 0,
+            depc: 0,
+            error_epc: 0,
+            isa: model.isa,
+            status_write_mask: Self::status_write_mask(model),
+        }
     }
 
-    #[inline]
-    pub fn write(&mut self, index: usize, value: u32) {
-        self.regs[index] = value;
+    fn status_write_mask(model: &CpuModel) -> u32 {
+        let mut mask = STATUS_WRITE_MASK;
+        if !model.fpu {
+            mask &= !STATUS_CU1;
+        }
+        if model.fir() & FIR_F64 == 0 {
+            mask &= !STATUS_FR;
+        }
+        mask
     }
 
-    #[inline]
-    pub fn status(&self) -> u32 {
-        self.regs[12]
+    /// MFC0 view of register `rd`, select `sel`.
+    pub fn read(&self, rd: usize, sel: usize) -> u32 {
+        let r2 = self.isa >= IsaRevision::R2;
+        match (rd, sel) {
+            (0, 0) => self.index,
+            (1, 0) => self.random,
+            (2, 0) => self.entry_lo0,
+            (3, 0) => self.entry_lo1,
+            (4, 0) => self.context,
+            (4, 2) if r2 => self.user_local,
+            (5, 0) => self.page_mask,
+            (6, 0) => self.wired,
+            (7, 0) if r2 => self.hwrena,
+            (8, 0) => self.badvaddr,
+            (9, 0) => self.count,
+            (10, 0) => self.entry_hi,
+            (11, 0) => self.compare,
+            (12, 0) => self.status.0,
+            (13, 0) => self.cause.0,
+            (14, 0) => self.epc,
+            (15, 0) => self.prid,
+            (15, 1) if r2 => self.ebase,
+            (15, 1) => EBASE_DEFAULT, // R1: fixed vector base
+            (16, 0..=3) => self.config[sel],
+            (17, 0) => self.lladdr,
+            (23, 0) => self.debug,
+            (24, 0) => self.depc,
+            (30, 0) => self.error_epc,
+            _ => 0,
+        }
     }
 
-    #[inline]
-    pub fn set_status(&mut self, value: u32) {
-        self.regs[12] = value;
+    /// MTC0 write: read-only bits and registers are preserved.
+    pub fn write(&mut self, rd: usize, sel: usize, value: u32) {
+        let tlb_entries = self.tlb_entries();
+        let r2 = self.isa >= IsaRevision::R2;
+        match (rd, sel) {
+            (0, 0) => self.index = (self.index & INDEX_P) | (value % tlb_entries),
+            (1, 0) => {} // Random is read-only
+            (2, 0) => self.entry_lo0 = value & ENTRYLO_WRITE_MASK,
+            (3, 0) => self.entry_lo1 = value & ENTRYLO_WRITE_MASK,
+            (4, 0) => {
+                self.context =
+                    (value & CONTEXT_PTEBASE_MASK) | (self.context & CONTEXT_BADVPN2_MASK)
+            }
+            (4, 2) if r2 => self.user_local = value,
+            (5, 0) => self.page_mask = value & PAGEMASK_MASK,
+            (6, 0) => {
+                // Writing Wired restarts Random at the top
+                self.wired = value % tlb_entries;
+                self.random = tlb_entries - 1;
+            }
+            (7, 0) if r2 => self.hwrena = value & HWRENA_WRITE_MASK,
+            (9, 0) => self.count = value,
+            (10, 0) => self.entry_hi = value & (ENTRYHI_VPN2_MASK | ENTRYHI_ASID_MASK),
+            (11, 0) => self.set_compare(value),
+            (12, 0) => Self::masked(&mut self.status.0, value, self.status_write_mask),
+            (13, 0) => Self::masked(&mut self.cause.0, value, CAUSE_WRITE_MASK),
+            (14, 0) => self.epc = value,
+            (15, 1) if r2 => Self::masked(&mut self.ebase, value, EBASE_WRITE_MASK),
+            (16, 0) => Self::masked(&mut self.config[0], value, CONFIG0_K0_MASK),
+            (23, 0) => Self::masked(&mut self.debug, value, DEBUG_WRITE_MASK),
+            (24, 0) => self.depc = value,
+            (30, 0) => self.error_epc = value,
+            _ => {}
+        }
     }
 
     #[inline]
-    pub fn cause(&self) -> u32 {
-        self.regs[13]
+    fn masked(reg: &mut u32, value: u32, mask: u32) {
+        *reg = (*reg & !mask) | (value & mask);
     }
 
+    /// Number of JTLB entries advertised in Config1.MMUSize.
     #[inline]
-    pub fn epc(&self) -> u32 {
-        self.regs[14]
+    pub fn tlb_entries(&self) -> u32 {
+        ((self.config[1] >> CONFIG1_MMU_SHIFT) & 0x3F) + 1
     }
 
-    #[inline]
-    pub fn set_epc(&mut self, value: u32) {
-        self.regs[14] = value;
+    /// Advances Count and raises the timer interrupt (TI / IP7) when it
+    /// reaches Compare.
+    pub fn tick_count(&mut self) {
+        self.count = self.count.wrapping_add(1);
+        if self.count == self.compare {
+            self.cause.set(CAUSE_TI | CAUSE_IP7, true);
+        }
+    }
+
+    /// Writing Compare acknowledges the timer interrupt.
+    pub fn set_compare(&mut self, value: u32) {
+        self.compare = value;
+        self.cause.set(CAUSE_TI | CAUSE_IP7, false);
     }
 
+    /// Records the faulting page of a TLB exception in Context and EntryHi.
+    pub fn set_tlb_fault(&mut self, vaddr: u32) {
+        self.context =
+            (self.context & !CONTEXT_BADVPN2_MASK) | ((vaddr >> 9) & CONTEXT_BADVPN2_MASK);
+        self.entry_hi = (vaddr & ENTRYHI_VPN2_MASK) | (self.entry_hi & ENTRYHI_ASID_MASK);
+    }
+
+    /// Current address space identifier (EntryHi.ASID).
     #[inline]
-    pub fn badvaddr(&self) -> u32 {
-        self.regs[8]
+    pub fn asid(&self) -> u8 {
+        (self.entry_hi & ENTRYHI_ASID_MASK) as u8
     }
 
-    pub fn dump(&self) {
-        println!("--- COP0 Registers ---");
-        for (i, r) in self.regs.iter().enumerate() {
-            println!("COP0[{:02}] = 0x{:08X}", i, r);
+    /// Base of the general exception vectors: the bootstrap area while
+    /// Status.BEV is set, EBase otherwise (fixed at 0x8000_0000 on R1).
+    pub fn exception_base(&self) -> u32 {
+        if self.status.bev() {
+            0xBFC0_0200
+        } else {
+            self.read(15, 1) & !0xFFF
         }
     }
 
-    pub fn set_status_bit(&mut self, bit: u32) {
-        self.regs[12] |= bit;
+    /// Debug.DM: executing the debug handler.
+    #[inline]
+    pub fn in_debug_mode(&self) -> bool {
+        self.debug & DEBUG_DM != 0
+    }
+
+    /// Debug mode, EXL or ERL force kernel mode; otherwise KSU selects the
+    /// level (0b11 is reserved and treated as user).
+    pub fn mode(&self) -> PrivilegeMode {
+        if self.in_debug_mode() || self.status.exl() || self.status.erl() {
+            return PrivilegeMode::Kernel;
+        }
+        match self.status.ksu() {
+            0 => PrivilegeMode::Kernel,
+            1 => PrivilegeMode::Supervisor,
+            _ => PrivilegeMode::User,
+        }
     }
 
-    pub fn clear_status_bit(&mut self, bit: u32) {
-        self.regs[12] &= !bit;
+    pub fn is_kernel_mode(&self) -> bool {
+        self.mode() == PrivilegeMode::Kernel
     }
 
-    pub fn status_bit(&self, bit: u32) -> bool {
-        (self.regs[12] & bit) != 0
+    pub fn dump(&self) {
+        const NAMES: [(usize, usize, &str); 26] = [
+            (0, 0, "Index"),
+            (1, 0, "Random"),
+            (2, 0, "EntryLo0"),
+            (3, 0, "EntryLo1"),
+            (4, 0, "Context"),
+            (4, 2, "UserLocal"),
+            (5, 0, "PageMask"),
+            (6, 0, "Wired"),
+            (7, 0, "HWREna"),
+            (8, 0, "BadVAddr"),
+            (9, 0, "Count"),
+            (10, 0, "EntryHi"),
+            (11, 0, "Compare"),
+            (12, 0, "Status"),
+            (13, 0, "Cause"),
+            (14, 0, "EPC"),
+            (15, 0, "PRId"),
+            (15, 1, "EBase"),
+            (16, 0, "Config0"),
+            (16, 1, "Config1"),
+            (16, 2, "Config2"),
+            (16, 3, "Config3"),
+            (17, 0, "LLAddr"),
+            (23, 0, "Debug"),
+            (24, 0, "DEPC"),
+            (30, 0, "ErrorEPC"),
+        ];
+        println!("--- COP0 Registers ---");
+        for (rd, sel, name) in NAMES {
+            println!("{:<9} ({:02}.{}) = 0x{:08X}", name, rd, sel, self.read(rd, sel));
+        }
     }
 }
 
@@ -94,24 +504,132 @@ impl Cop1 {
         Self::default()
     }
 
+    /// Reset state for `model`, with FIR describing its FPU.
+    pub fn for_model(model: &CpuModel) -> Self {
+        Cop1 {
+            fir: model.fir(),
+            ..Self::default()
+        }
+    }
+
+    /// Raw 64-bit contents of a single register, ignoring Status.FR.
     #[inline]
-    pub fn read_f(&self, index: usize) -> f64 {
+    pub fn read_bits(&self, index: usize) -> u64 {
         self.f[index]
     }
 
     #[inline]
-    pub fn write_f(&mut self, index: usize, value: f64) {
-        self.f[index] = value;
+    pub fn write_bits(&mut self, index: usize, bits: u64) {
+        self.f[index] = bits;
+    }
+
+    #[inline]
+    pub fn read_s(&self, index: usize) -> f32 {
+        f32::from_bits(self.read_w(index))
     }
 
     #[inline]
-    pub fn read_bits(&self, index: usize) -> u64 {
-        self.f[index].to_bits()
+    pub fn write_s(&mut self, index: usize, value: f32) {
+        self.write_w(index, value.to_bits());
     }
 
     #[inline]
-    pub fn write_bits(&mut self, index: usize, bits: u64) {
-        self.f[index] = f64::from_bits(bits);
+    pub fn read_d(&self, index: usize) -> f64 {
+        f64::from_bits(self.read_l(index))
+    }
+
+    #[inline]
+    pub fn write_d(&mut self, index: usize, value: f64) {
+        self.write_l(index, value.to_bits());
+    }
+
+    /// 64-bit view (D / L formats). With FR=0 the low word is in the even
+    /// register and the high word in the odd one.
+    pub fn read_l(&self, index: usize) -> u64 {
+        if self.fr {
+            self.f[index]
+        } else {
+            let even = index & !1;
+            (self.read_w(even) as u64) | ((self.read_w(even + 1) as u64) << 32)
+        }
+    }
+
+    pub fn write_l(&mut self, index: usize, value: u64) {
+        if self.fr {
+            self.f[index] = value;
+        } else {
+            let even = index & !1;
+            self.write_w(even, value as u32);
+            self.write_w(even + 1, (value >> 32) as u32);
+        }
+    }
+
+    /// 32-bit view (S / W formats, MFC1 / MTC1): the low word of the
+    /// register. Writes leave the upper word untouched.
+    #[inline]
+    pub fn read_w(&self, index: usize) -> u32 {
+        self.f[index] as u32
+    }
+
+    #[inline]
+    pub fn write_w(&mut self, index: usize, value: u32) {
+        self.f[index] = (self.f[index] & !0xFFFF_FFFF) | value as u64;
+    }
+
+    /// FCSR condition code `cc` (0-7).
+    pub fn condition(&self, cc: u8) -> bool {
+        self.fcsr & Self::cc_bit(cc) != 0
+    }
+
+    pub fn set_condition(&mut self, cc: u8, value: bool) {
+        if value {
+            self.fcsr |= Self::cc_bit(cc);
+        } else {
+            self.fcsr &= !Self::cc_bit(cc);
+        }
+    }
+
+    #[inline]
+    fn cc_bit(cc: u8) -> u32 {
+        if cc == 0 { FCSR_CC0 } else { 1 << (24 + cc as u32) }
+    }
+
+    /// CFC1 view of the FPU control registers.
+    pub fn read_control(&self, index: usize) -> u32 {
+        match index {
+            0 => self.fir,
+            25 => ((self.fcsr >> 24) & 0xFE) | ((self.fcsr >> 23) & 1), // FCCR
+            26 => self.fcsr & 0x0003_F07C,                              // FEXR
+            28 => (self.fcsr & 0x0000_0F83) | ((self.fcsr >> 22) & 0x4), // FENR
+            31 => self.fcsr,
+            _ => 0,
+        }
+    }
+
+    /// CTC1 write to the FPU control registers. The write always happens;
+    /// if it leaves a Cause bit set together with its Enable (or Cause.E
+    /// set), a Floating-Point exception is signalled afterwards.
+    pub fn write_control(&mut self, index: usize, value: u32) -> Result<(), Exception> {
+        match index {
+            25 => {
+                let cc = ((value & 0xFE) << 24) | ((value & 1) << 23);
+                self.fcsr = (self.fcsr & !0xFE80_0000) | cc;
+            }
+            26 => self.fcsr = (self.fcsr & !0x0003_F07C) | (value & 0x0003_F07C),
+            28 => {
+                let fen = (value & 0x0000_0F83) | ((value & 0x4) << 22);
+                self.fcsr = (self.fcsr & !0x0100_0F83) | fen;
+            }
+            31 => self.fcsr = value & FCSR_WRITE_MASK,
+            _ => {}
+        }
+
+        let cause = (self.fcsr >> 12) & 0x3F;
+        let enables = (self.fcsr >> 7) & 0x1F;
+        if cause & (enables | FPE_UNIMPLEMENTED) != 0 {
+            return Err(Exception::new(ExceptionCode::FloatingPoint));
+        }
+        Ok(())
     }
 
     #[inline]
@@ -132,7 +650,13 @@ impl Cop1 {
     pub fn dump(&self) {
         println!("--- COP1 (FPU) Registers ---");
         for (i, r) in self.f.iter().enumerate() {
-            println!("F{:02} = {:>20.10}", i, r);
+            println!(
+                "F{:02} = 0x{:016X}  s={:<16e} d={:e}",
+                i,
+                r,
+                f32::from_bits(*r as u32),
+                f64::from_bits(*r)
+            );
         }
         println!("FCSR = 0x{:08X}", self.fcsr);
         println!("FIR  = 0x{:08X}", self.fir);
@@ -1,23 +1,69 @@
 use crate::alu::*;
 use crate::cop::*;
+use crate::devices::intc::IrqLines;
+use crate::exception::*;
+use crate::fpu::*;
 use crate::instructions::*;
 use crate::memory::*;
+use crate::model::*;
 use crate::registers::*;
+use crate::syscall::*;
+use crate::tlb::*;
+
+/// How often Cop0 Count advances relative to CPU steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CountRate {
+    EveryCycle,
+    EveryOtherCycle,
+}
 
 pub struct CPU {
+    pub model: CpuModel,
     pub registers: Registers,
     pub cop0: Cop0,
     pub cop1: Cop1,
     pub alu: ALU,
+    pub tlb: Tlb,
+    /// Address of the instruction being executed (the branch when in a delay slot).
+    pub current_pc: u32,
+    pub in_delay_slot: bool,
+    /// Target of a taken branch; applied once its delay slot has executed.
+    pub pending_branch: Option<u32>,
+    /// Set by WAIT; the CPU stalls until an interrupt wakes it.
+    pub waiting: bool,
+    /// Interrupt controller output, wired to Cause.IP2.
+    pub irq: Option<IrqLines>,
+    pub count_rate: CountRate,
+    count_phase: bool,
+    pub syscall_handler: Option<Box<dyn SyscallHandler>>,
+    /// LLbit and the physical word linked by the last LL.
+    pub ll_bit: bool,
+    pub ll_addr: u32,
 }
 
 impl CPU {
     pub fn new() -> Self {
+        Self::with_model(CpuModel::default())
+    }
+
+    pub fn with_model(model: CpuModel) -> Self {
         let mut cpu = Self {
+            model,
             registers: Registers::default(),
-            cop0: Cop0::default(),
-            cop1: Cop1::default(),
+            cop0: Cop0::for_model(&model),
+            cop1: Cop1::for_model(&model),
             alu: ALU,
+            tlb: Tlb::new(model.tlb_entries() as usize),
+            current_pc: 0,
+            in_delay_slot: false,
+            pending_branch: None,
+            waiting: false,
+            irq: None,
+            count_rate: CountRate::EveryOtherCycle,
+            count_phase: false,
+            syscall_handler: None,
+            ll_bit: false,
+            ll_addr: 0,
         };
         cpu.reset();
         cpu
@@ -25,8 +71,13 @@ impl CPU {
 
     pub fn reset(&mut self) {
         self.registers.reset();
-        self.cop0 = Cop0::default();
-        self.cop1 = Cop1::default();
+        self.cop0 = Cop0::for_model(&self.model);
+        self.cop1 = Cop1::for_model(&self.model);
+        self.in_delay_slot = false;
+        self.pending_branch = None;
+        self.waiting = false;
+        self.count_phase = false;
+        self.ll_bit = false;
 
         let sp = 0x8000_0000 + 0x0010_0000 - 0x1000;
         self.registers.set_sp(sp);
@@ -40,37 +91,669 @@ impl CPU {
         );
     }
 
+    pub fn connect_irq(&mut self, lines: IrqLines) {
+        self.irq = Some(lines);
+    }
+
+    pub fn set_syscall_handler(&mut self, handler: Box<dyn SyscallHandler>) {
+        self.syscall_handler = Some(handler);
+    }
+
     pub fn step(&mut self, bus: &mut MemoryBus) {
+        self.tick_timer();
+        if self.poll_interrupts() {
+            return;
+        }
+        if self.waiting {
+            return;
+        }
+
+        let delay_target = self.begin_instruction();
+        self.tick_random();
+
         let instr_word = match self.fetch(bus) {
-            Some(v) => v,
-            None => return,
+            Ok(v) => v,
+            Err(e) => return self.raise_exception(e),
         };
 
         let decoded = self.decode(instr_word);
-        let exec_result = self.execute(bus, decoded);
-        self.writeback(exec_result);
+        match self.execute(bus, decoded) {
+            Ok(exec_result) => {
+                // The delay slot has run: control transfers to the branch
+                // target, unless it entered debug mode (which clears the slot)
+                if let Some(target) = delay_target.filter(|_| self.in_delay_slot) {
+                    self.registers.set_pc(target);
+                }
+                self.writeback(exec_result)
+            }
+            Err(e) => self.raise_exception(e),
+        }
+    }
+
+    /// Consumes the pending branch, if any, and records the address an
+    /// exception on the next instruction reports: the branch itself when
+    /// that instruction is its delay slot.
+    fn begin_instruction(&mut self) -> Option<u32> {
+        let pc = self.registers.get_pc();
+        let delay_target = self.pending_branch.take();
+        self.in_delay_slot = delay_target.is_some();
+        self.current_pc = if self.in_delay_slot {
+            pc.wrapping_sub(4)
+        } else {
+            pc
+        };
+        delay_target
     }
 
-    pub fn fetch(&mut self, bus: &mut MemoryBus) -> Option<u32> {
+    pub fn fetch(&mut self, bus: &mut MemoryBus) -> Result<u32, Exception> {
         let pc = self.registers.get_pc();
-        match bus.read32_virt(pc) {
-            Ok(instr) => {
-                self.registers.special.pc = pc.wrapping_add(4);
-                Some(instr)
+        MemoryBus::check_alignment(pc, 4, false)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Fetch))?;
+        let paddr = self.translate(pc, MemAccess::Fetch)?;
+        let instr = bus
+            .read32(paddr)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Fetch))?;
+        self.registers.special.pc = pc.wrapping_add(4);
+        Ok(instr)
+    }
+
+    /// Maps a virtual address for the current privilege mode. KSEG0/KSEG1
+    /// are fixed windows onto the low 512 MiB, KUSEG and KSEG2/KSEG3 go
+    /// through the TLB. While ERL is set KUSEG is an unmapped identity
+    /// window (reset/bootstrap state).
+    ///
+    /// User mode may only touch KUSEG; supervisor mode additionally sees
+    /// SSEG (0xC000_0000-0xDFFF_FFFF). Anything else is an address error.
+    pub fn translate(&self, vaddr: u32, access: MemAccess) -> Result<u32, Exception> {
+        let allowed = match self.cop0.mode() {
+            PrivilegeMode::Kernel => true,
+            PrivilegeMode::Supervisor => {
+                vaddr < 0x8000_0000 || (0xC000_0000..0xE000_0000).contains(&vaddr)
+            }
+            PrivilegeMode::User => vaddr < 0x8000_0000,
+        };
+        if !allowed {
+            let code = match access {
+                MemAccess::Store => ExceptionCode::AddressErrorStore,
+                _ => ExceptionCode::AddressErrorLoad,
+            };
+            return Err(Exception::with_bad_vaddr(code, vaddr));
+        }
+
+        match vaddr {
+            0x0000_0000..=0x7FFF_FFFF if self.cop0.status.erl() => Ok(vaddr),
+            0x8000_0000..=0x9FFF_FFFF => Ok(vaddr - 0x8000_0000), // KSEG0
+            0xA000_0000..=0xBFFF_FFFF => Ok(vaddr - 0xA000_0000), // KSEG1
+            _ => self
+                .tlb
+                .translate(vaddr, self.cop0.asid(), access == MemAccess::Store)
+                .map_err(|f| Exception::from_tlb(f, vaddr, access)),
+        }
+    }
+
+    /// Random counts down from the last TLB entry to Wired, once per
+    /// instruction, and selects the slot written by TLBWR.
+    fn tick_random(&mut self) {
+        let last = self.tlb.len() as u32 - 1;
+        let random = self.cop0.random;
+        self.cop0.random = if random <= self.cop0.wired || random > last {
+            last
+        } else {
+            random - 1
+        };
+    }
+
+    fn tick_timer(&mut self) {
+        match self.count_rate {
+            CountRate::EveryCycle => self.cop0.tick_count(),
+            CountRate::EveryOtherCycle => {
+                self.count_phase = !self.count_phase;
+                if self.count_phase {
+                    self.cop0.tick_count();
+                }
+            }
+        }
+    }
+
+    /// Samples the hardware lines into Cause.IP and takes the interrupt
+    /// exception when enabled. Returns true if an interrupt was taken.
+    fn poll_interrupts(&mut self) -> bool {
+        if let Some(irq) = &self.irq {
+            self.cop0.cause.set(CAUSE_IP2, irq.asserted());
+        }
+
+        let pending = self.cop0.cause.ip() & self.cop0.status.im();
+        if pending == 0 || self.cop0.in_debug_mode() {
+            return false;
+        }
+        // WAIT resumes on any unmasked request, even with IE clear
+        self.waiting = false;
+
+        let status = self.cop0.status;
+        if !status.ie() || status.exl() || status.erl() {
+            return false;
+        }
+
+        // Interrupting a delay slot reports the branch (BD set), which is
+        // re-executed on return
+        self.begin_instruction();
+        self.raise_exception(Exception::new(ExceptionCode::Interrupt));
+        true
+    }
+
+    /// Schedules a control transfer to `target` after the delay slot (the
+    /// instruction at the current PC) has executed.
+    #[inline]
+    fn branch_to(&mut self, target: u32) {
+        self.pending_branch = Some(target);
+    }
+
+    /// PC-relative branch: `pc_next` is the delay slot address.
+    fn branch(&mut self, taken: bool, pc_next: u32, offset: u32) -> Result<u32, Exception> {
+        if taken {
+            self.branch_to(pc_next.wrapping_add(offset));
+        }
+        Ok(0)
+    }
+
+    /// Branch-likely: the delay slot only executes when the branch is
+    /// taken; otherwise it is annulled and execution resumes after it.
+    fn branch_likely(&mut self, taken: bool, pc_next: u32, offset: u32) -> Result<u32, Exception> {
+        if taken {
+            self.branch_to(pc_next.wrapping_add(offset));
+        } else {
+            self.registers.set_pc(pc_next.wrapping_add(4));
+        }
+        Ok(0)
+    }
+
+    /// Delivers `exc` through Cop0 and redirects the PC to the exception vector.
+    pub fn raise_exception(&mut self, exc: Exception) {
+        self.pending_branch = None;
+
+        let mut cause = self.cop0.cause;
+        cause.set_exc_code(exc.code as u32);
+        cause.set_ce(exc.coprocessor);
+
+        // EPC and BD are frozen while EXL is set (nested exception)
+        let exl = self.cop0.status.exl();
+        if !exl {
+            self.cop0.epc = self.current_pc;
+            cause.set(CAUSE_BD, self.in_delay_slot);
+        }
+        self.cop0.cause = cause;
+
+        if let Some(vaddr) = exc.bad_vaddr {
+            self.cop0.badvaddr = vaddr;
+            if exc.is_tlb() {
+                self.cop0.set_tlb_fault(vaddr);
+            }
+        }
+
+        self.cop0.status.set(STATUS_EXL, true);
+        self.in_delay_slot = false;
+
+        // TLB refills use the dedicated vector unless already at EXL
+        let offset = if exc.refill && !exl {
+            0x000
+        } else if exc.code == ExceptionCode::Interrupt && cause.iv() {
+            0x200
+        } else {
+            0x180
+        };
+        let base = self.cop0.exception_base();
+        self.registers.set_pc(base + offset);
+    }
+
+    /// EJTAG debug exception (SDBBP): DEPC/Debug.DBD record the faulting
+    /// instruction like EPC/Cause.BD, Debug.DM is set and execution moves
+    /// to the debug vector. Status and Cause are left untouched, so this
+    /// bypasses `raise_exception`.
+    fn enter_debug_mode(&mut self) {
+        let mut debug = self.cop0.debug & !(DEBUG_DBD | 0x3F);
+        if self.in_delay_slot {
+            debug |= DEBUG_DBD;
+        }
+        self.cop0.debug = debug | DEBUG_DM | DEBUG_DBP;
+        self.cop0.depc = self.current_pc;
+        self.in_delay_slot = false;
+        self.registers.set_pc(DEBUG_VECTOR);
+    }
+
+    /// SYSCALL / BREAK / SDBBP: offer the trap to the host handler,
+    /// otherwise raise the Sys / Bp exception or enter debug mode.
+    fn host_trap(
+        &mut self,
+        bus: &mut MemoryBus,
+        kind: HostTrap,
+        code: u32,
+    ) -> Result<u32, Exception> {
+        if let Some(mut handler) = self.syscall_handler.take() {
+            let handled = handler.handle(kind, code, &mut self.registers, bus);
+            self.syscall_handler = Some(handler);
+            if handled {
+                return Ok(0);
+            }
+        }
+
+        match kind {
+            HostTrap::Syscall => Err(Exception::new(ExceptionCode::Syscall)),
+            HostTrap::Break => Err(Exception::new(ExceptionCode::Breakpoint)),
+            HostTrap::Sdbbp => {
+                self.enter_debug_mode();
+                Ok(0)
+            }
+        }
+    }
+
+    fn execute_cop0(&mut self, c: Cop0Ins) -> Result<u32, Exception> {
+        if !self.cop0.is_kernel_mode() && !self.cop0.status.cu(0) {
+            return Err(Exception::coprocessor_unusable(0));
+        }
+
+        let rd = c.rd as usize;
+        let sel = c.sel as usize;
+        match c.rs {
+            0x00 => {
+                // MFC0 rt, rd, sel
+                let val = self.cop0.read(rd, sel);
+                self.registers.write(c.rt as usize, val);
+                Ok(val)
+            }
+            0x04 => {
+                // MTC0 rt, rd, sel
+                let val = self.registers.read(c.rt as usize);
+                self.cop0.write(rd, sel, val);
+                Ok(0)
+            }
+            0x0A | 0x0E if self.is_r2() => {
+                // RDPGPR rd, rt / WRPGPR rd, rt: no shadow register sets
+                // (SRSCtl.HSS = 0), so the previous set is the current one
+                let val = self.registers.read(c.rt as usize);
+                self.registers.write(rd, val);
+                Ok(val)
+            }
+            0x0B if rd == 12 && sel == 0 && self.model.isa >= IsaRevision::R2 => {
+                // MFMC0: DI / EI (sc bit selects EI), Release 2 only
+                let status = self.cop0.status.0;
+                self.registers.write(c.rt as usize, status);
+                self.cop0.status.set(STATUS_IE, c.funct & 0x20 != 0);
+                Ok(status)
+            }
+            0x10..=0x1F => match c.funct {
+                0x01 => {
+                    // TLBR
+                    let e = self.tlb.read((self.cop0.index & !INDEX_P) as usize);
+                    let g = e.global as u32;
+                    self.cop0.page_mask = e.page_mask;
+                    self.cop0.entry_hi = e.entry_hi;
+                    self.cop0.entry_lo0 = e.lo[0] | g;
+                    self.cop0.entry_lo1 = e.lo[1] | g;
+                    Ok(0)
+                }
+                0x02 | 0x06 => {
+                    // TLBWI / TLBWR
+                    let index = if c.funct == 0x02 {
+                        self.cop0.index & !INDEX_P
+                    } else {
+                        self.cop0.random
+                    };
+                    let (lo0, lo1) = (self.cop0.entry_lo0, self.cop0.entry_lo1);
+                    let page_mask = self.cop0.page_mask;
+                    let entry = TlbEntry {
+                        page_mask,
+                        entry_hi: self.cop0.entry_hi & !page_mask,
+                        lo: [lo0 & !ENTRYLO_G, lo1 & !ENTRYLO_G],
+                        global: lo0 & lo1 & ENTRYLO_G != 0,
+                    };
+                    self.tlb.write(index as usize, entry);
+                    Ok(0)
+                }
+                0x08 => {
+                    // TLBP
+                    self.cop0.index = match self.tlb.probe(self.cop0.entry_hi) {
+                        Some(i) => i as u32,
+                        None => INDEX_P | (self.cop0.index & !INDEX_P),
+                    };
+                    Ok(0)
+                }
+                0x18 => {
+                    // ERET
+                    self.ll_bit = false;
+                    if self.cop0.status.erl() {
+                        self.registers.set_pc(self.cop0.error_epc);
+                        self.cop0.status.set(STATUS_ERL, false);
+                    } else {
+                        self.registers.set_pc(self.cop0.epc);
+                        self.cop0.status.set(STATUS_EXL, false);
+                    }
+                    Ok(0)
+                }
+                0x1F if self.cop0.in_debug_mode() => {
+                    // DERET
+                    self.registers.set_pc(self.cop0.depc);
+                    self.cop0.debug &= !DEBUG_DM;
+                    Ok(0)
+                }
+                0x20 => {
+                    // WAIT
+                    self.waiting = true;
+                    Ok(0)
+                }
+                _ => Err(Exception::new(ExceptionCode::ReservedInstruction)),
+            },
+            _ => Err(Exception::new(ExceptionCode::ReservedInstruction)),
+        }
+    }
+
+    #[inline]
+    fn is_r2(&self) -> bool {
+        self.model.isa >= IsaRevision::R2
+    }
+
+    fn is_rotate(&self, r: RType) -> bool {
+        let flag = if r.funct == 0x02 { r.rs } else { r.shamt };
+        flag == 1 && self.is_r2()
+    }
+
+    /// SPECIAL3 (Release 2): bitfield ops, byte shuffles and RDHWR.
+    fn execute_special3(&mut self, s: Special3) -> Result<u32, Exception> {
+        let reserved = Exception::new(ExceptionCode::ReservedInstruction);
+        if !self.is_r2() {
+            return Err(reserved);
+        }
+
+        let rs_val = self.registers.read(s.rs as usize);
+        let rt_val = self.registers.read(s.rt as usize);
+        let (dest, val) = match s.funct {
+            0x00 => {
+                // EXT rt, rs, pos, size (rd = size - 1, sa = pos)
+                let mask = (1u64 << (s.rd as u32 + 1)) - 1;
+                (s.rt, ((rs_val as u64 >> s.sa) & mask) as u32)
+            }
+            0x04 => {
+                // INS rt, rs, pos, size (rd = pos + size - 1, sa = pos)
+                let high = (1u64 << (s.rd as u32 + 1)) - 1;
+                let low = (1u64 << s.sa) - 1;
+                let mask = (high & !low) as u32;
+                (s.rt, (rt_val & !mask) | ((rs_val << s.sa) & mask))
+            }
+            0x20 => match s.sa {
+                // BSHFL
+                0x02 => {
+                    // WSBH rd, rt
+                    let v = ((rt_val & 0x00FF_00FF) << 8) | ((rt_val >> 8) & 0x00FF_00FF);
+                    (s.rd, v)
+                }
+                0x10 => (s.rd, rt_val as u8 as i8 as i32 as u32), // SEB
+                0x18 => (s.rd, rt_val as u16 as i16 as i32 as u32), // SEH
+                _ => return Err(reserved),
+            },
+            0x3B => (s.rt, self.read_hwr(s.rd)?), // RDHWR rt, rd
+            _ => return Err(reserved),
+        };
+        self.registers.write(dest as usize, val);
+        Ok(val)
+    }
+
+    /// RDHWR: user mode needs the matching HWREna bit.
+    fn read_hwr(&self, reg: u8) -> Result<u32, Exception> {
+        let reserved = Exception::new(ExceptionCode::ReservedInstruction);
+        if !self.cop0.is_kernel_mode() && self.cop0.hwrena & (1 << reg) == 0 {
+            return Err(reserved);
+        }
+        match reg {
+            0 => Ok(self.cop0.ebase & 0x3FF), // CPUNum
+            1 => {
+                // SYNCI_Step: smallest L1 line size (0 without caches)
+                let lines = [self.model.icache, self.model.dcache];
+                Ok(lines.iter().flatten().map(|c| c.line_bytes).min().unwrap_or(0))
+            }
+            2 => Ok(self.cop0.count), // CC
+            3 => Ok(match self.count_rate {
+                // CCRes: CPU cycles per Count increment
+                CountRate::EveryCycle => 1,
+                CountRate::EveryOtherCycle => 2,
+            }),
+            29 if self.cop0.config[3] & CONFIG3_ULRI != 0 => Ok(self.cop0.user_local),
+            _ => Err(reserved),
+        }
+    }
+
+    /// Bit offset of the byte at `addr` inside its aligned word, counted
+    /// from the least significant end (used by LWL/LWR/SWL/SWR).
+    fn byte_lane(addr: u32, little_endian: bool) -> u32 {
+        let off = addr & 3;
+        if little_endian {
+            off * 8
+        } else {
+            (3 - off) * 8
+        }
+    }
+
+    /// Checks Status.CU1 and latches Status.FR into the FPU register file.
+    fn cop1_usable(&mut self) -> Result<(), Exception> {
+        if !self.cop0.status.cu(1) {
+            return Err(Exception::coprocessor_unusable(1));
+        }
+        self.cop1.fr = self.cop0.status.fr();
+        Ok(())
+    }
+
+    /// LDC1 / LDXC1 / LUXC1: doubleword in bus order into `ft`.
+    fn load_fpr64(&mut self, bus: &mut MemoryBus, addr: u32, ft: usize) -> Result<(), Exception> {
+        MemoryBus::check_alignment(addr, 8, false)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Load))?;
+        let w0 = self.load32(bus, addr)?;
+        let w1 = self.load32(bus, addr.wrapping_add(4))?;
+        let (lo, hi) = if bus.little_endian {
+            (w0, w1)
+        } else {
+            (w1, w0)
+        };
+        self.cop1.write_l(ft, ((hi as u64) << 32) | lo as u64);
+        Ok(())
+    }
+
+    /// SDC1 / SDXC1 / SUXC1: `ft` as a doubleword in bus order.
+    fn store_fpr64(&mut self, bus: &mut MemoryBus, addr: u32, ft: usize) -> Result<(), Exception> {
+        MemoryBus::check_alignment(addr, 8, true)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Store))?;
+        let val = self.cop1.read_l(ft);
+        let (lo, hi) = (val as u32, (val >> 32) as u32);
+        let (w0, w1) = if bus.little_endian {
+            (lo, hi)
+        } else {
+            (hi, lo)
+        };
+        self.store32(bus, addr, w0)?;
+        self.store32(bus, addr.wrapping_add(4), w1)
+    }
+
+    /// COP1X (Release 2): indexed FPU loads/stores, PREFX and the
+    /// multiply-add family. LUXC1/SUXC1 need a 64-bit FPU.
+    fn execute_cop1x(&mut self, bus: &mut MemoryBus, i: IType) -> Result<u32, Exception> {
+        let reserved = Exception::new(ExceptionCode::ReservedInstruction);
+        if !self.is_r2() || !self.model.fpu {
+            return Err(reserved);
+        }
+        self.cop1_usable()?;
+
+        let fs = ((i.imm >> 11) & 0x1F) as u8;
+        let fd = ((i.imm >> 6) & 0x1F) as u8;
+        let funct = (i.imm & 0x3F) as u8;
+        let addr = self
+            .registers
+            .read(i.rs as usize)
+            .wrapping_add(self.registers.read(i.rt as usize));
+
+        match funct {
+            0x00 => {
+                // LWXC1 fd, index(base)
+                let val = self.load32(bus, addr)?;
+                self.cop1.write_w(fd as usize, val);
+                Ok(val)
+            }
+            0x08 => {
+                // SWXC1 fs, index(base)
+                let val = self.cop1.read_w(fs as usize);
+                self.store32(bus, addr, val)?;
+                Ok(0)
             }
-            Err(e) => {
-                println!("Fetch error at PC {:#010X}: {:?}", pc, e);
-                None
+            0x01 | 0x05 | 0x09 | 0x0D => {
+                // LDXC1 / LUXC1 / SDXC1 / SUXC1 (U: low three bits ignored)
+                let unaligned = funct & 0x04 != 0;
+                if unaligned && self.cop1.fir() & FIR_F64 == 0 {
+                    return Err(reserved);
+                }
+                let addr = if unaligned { addr & !7 } else { addr };
+                if funct & 0x08 == 0 {
+                    self.load_fpr64(bus, addr, fd as usize)?;
+                } else {
+                    self.store_fpr64(bus, addr, fs as usize)?;
+                }
+                Ok(0)
             }
+            0x0F => Ok(0), // PREFX hint, index(base)
+            0x20..=0x3F => {
+                // MADD / MSUB / NMADD / NMSUB.fmt fd, fr, fs, ft
+                FPU::execute_madd(&mut self.cop1, funct, i.rs, fs, i.rt, fd)?;
+                Ok(0)
+            }
+            _ => Err(reserved),
         }
     }
 
+    fn execute_cop1(&mut self, c: Cop1Ins) -> Result<u32, Exception> {
+        self.cop1_usable()?;
+
+        let rt = c.ft as usize;
+        let fs = c.fs as usize;
+        match c.fmt {
+            0x00 => {
+                // MFC1 rt, fs
+                let val = self.cop1.read_w(fs);
+                self.registers.write(rt, val);
+                Ok(val)
+            }
+            0x02 => {
+                // CFC1 rt, fs
+                let val = self.cop1.read_control(fs);
+                self.registers.write(rt, val);
+                Ok(val)
+            }
+            0x03 if self.is_r2() => {
+                // MFHC1 rt, fs
+                let val = (self.cop1.read_l(fs) >> 32) as u32;
+                self.registers.write(rt, val);
+                Ok(val)
+            }
+            0x04 => {
+                // MTC1 rt, fs
+                self.cop1.write_w(fs, self.registers.read(rt));
+                Ok(0)
+            }
+            0x06 => {
+                // CTC1 rt, fs
+                self.cop1.write_control(fs, self.registers.read(rt))?;
+                Ok(0)
+            }
+            0x07 if self.is_r2() => {
+                // MTHC1 rt, fs
+                let low = self.cop1.read_l(fs) & 0xFFFF_FFFF;
+                let high = self.registers.read(rt) as u64;
+                self.cop1.write_l(fs, (high << 32) | low);
+                Ok(0)
+            }
+            0x08 => {
+                // BC1F / BC1T / BC1FL / BC1TL
+                let cc = c.ft >> 2;
+                let likely = c.ft & 0x2 != 0;
+                let on_true = c.ft & 0x1 != 0;
+                let offset = (((c.encode() & 0xFFFF) as i16 as i32) << 2) as u32;
+                let pc_next = self.registers.get_pc();
+
+                let taken = self.cop1.condition(cc) == on_true;
+                if likely {
+                    self.branch_likely(taken, pc_next, offset)
+                } else {
+                    self.branch(taken, pc_next, offset)
+                }
+            }
+            0x10 | 0x11 if matches!(c.funct, 0x11..=0x13) => {
+                // MOVF.fmt / MOVT.fmt / MOVZ.fmt / MOVN.fmt fd, fs, (cc | rt):
+                // raw copies, no FPU exceptions
+                let take = match c.funct {
+                    0x11 => self.cop1.condition(c.ft >> 2) == (c.ft & 0x1 != 0),
+                    0x12 => self.registers.read(c.ft as usize) == 0,
+                    _ => self.registers.read(c.ft as usize) != 0,
+                };
+                if take {
+                    let fd = c.fd as usize;
+                    if c.fmt == FMT_S {
+                        self.cop1.write_w(fd, self.cop1.read_w(fs));
+                    } else {
+                        self.cop1.write_l(fd, self.cop1.read_l(fs));
+                    }
+                }
+                Ok(0)
+            }
+            _ => {
+                FPU::execute(&mut self.cop1, c)?;
+                Ok(0)
+            }
+        }
+    }
+
+    /// Alignment check and translation for a data access of `size` bytes.
+    fn data_paddr(&self, vaddr: u32, size: u32, access: MemAccess) -> Result<u32, Exception> {
+        MemoryBus::check_alignment(vaddr, size, access == MemAccess::Store)
+            .map_err(|e| Exception::from_memory(e, access))?;
+        self.translate(vaddr, access)
+    }
+
+    fn load8(&mut self, bus: &mut MemoryBus, vaddr: u32) -> Result<u8, Exception> {
+        let paddr = self.data_paddr(vaddr, 1, MemAccess::Load)?;
+        bus.read8(paddr)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Load))
+    }
+
+    fn load16(&mut self, bus: &mut MemoryBus, vaddr: u32) -> Result<u16, Exception> {
+        let paddr = self.data_paddr(vaddr, 2, MemAccess::Load)?;
+        bus.read16(paddr)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Load))
+    }
+
+    fn load32(&mut self, bus: &mut MemoryBus, vaddr: u32) -> Result<u32, Exception> {
+        let paddr = self.data_paddr(vaddr, 4, MemAccess::Load)?;
+        bus.read32(paddr)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Load))
+    }
+
+    fn store8(&mut self, bus: &mut MemoryBus, vaddr: u32, val: u8) -> Result<(), Exception> {
+        let paddr = self.data_paddr(vaddr, 1, MemAccess::Store)?;
+        bus.write8(paddr, val)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Store))
+    }
+
+    fn store16(&mut self, bus: &mut MemoryBus, vaddr: u32, val: u16) -> Result<(), Exception> {
+        let paddr = self.data_paddr(vaddr, 2, MemAccess::Store)?;
+        bus.write16(paddr, val)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Store))
+    }
+
+    fn store32(&mut self, bus: &mut MemoryBus, vaddr: u32, val: u32) -> Result<(), Exception> {
+        let paddr = self.data_paddr(vaddr, 4, MemAccess::Store)?;
+        bus.write32(paddr, val)
+            .map_err(|e| Exception::from_memory(e, MemAccess::Store))
+    }
+
     pub fn decode(&self, instr: u32) -> Instruction {
         let decoded = Instruction::decode(instr);
         decoded
     }
 
-    pub fn execute(&mut self, bus: &mut MemoryBus, instr: Instruction) -> u32 {
+    pub fn execute(&mut self, bus: &mut MemoryBus, instr: Instruction) -> Result<u32, Exception> {
         match instr {
             Instruction::RType(r) => {
                 let rs_val = self.registers.read(r.rs as usize);
@@ -81,27 +764,44 @@ impl CPU {
                 match r.funct {
                     0x08 => {
                         // JR
-                        let target = rs_val;
-                        if let Some(v) = self.fetch(bus) {
-                            let d = self.decode(v);
-                            self.execute(bus, d);
-                        }
-                        self.registers.special.pc = target;
-                        return 0;
+                        self.branch_to(rs_val);
+                        return Ok(0);
                     }
                     0x09 => {
                         // JALR
                         let link = self.registers.get_pc().wrapping_add(4); // PC + 8
-                        let target = rs_val;
-                        if r.rd != 0 {
-                            self.registers.write(r.rd as usize, link);
+                        self.registers.write(r.rd as usize, link);
+                        self.branch_to(rs_val);
+                        return Ok(0);
+                    }
+                    0x01 => {
+                        // MOVF / MOVT rd, rs, cc
+                        self.cop1_usable()?;
+                        if r.rt & 0x2 != 0 {
+                            return Err(Exception::new(ExceptionCode::ReservedInstruction));
                         }
-                        if let Some(v) = self.fetch(bus) {
-                            let d = self.decode(v);
-                            self.execute(bus, d);
+                        let on_true = r.rt & 0x1 != 0;
+                        if self.cop1.condition(r.rt >> 2) == on_true {
+                            self.registers.write(r.rd as usize, rs_val);
                         }
-                        self.registers.special.pc = target;
-                        return 0;
+                        return Ok(0);
+                    }
+                    0x02 | 0x06 if self.is_rotate(r) => {
+                        // ROTR / ROTRV: R2 reuse of SRL's rs bit and SRLV's sa bit
+                        let amount = if r.funct == 0x02 { shamt as u32 } else { rs_val };
+                        let val = rt_val.rotate_right(amount & 0x1F);
+                        self.registers.write(r.rd as usize, val);
+                        return Ok(val);
+                    }
+                    0x0C => {
+                        // SYSCALL
+                        let code = (r.encode() >> 6) & 0xF_FFFF;
+                        return self.host_trap(bus, HostTrap::Syscall, code);
+                    }
+                    0x0D => {
+                        // BREAK
+                        let code = (r.encode() >> 6) & 0xF_FFFF;
+                        return self.host_trap(bus, HostTrap::Break, code);
                     }
 
                     _ => {}
@@ -120,6 +820,19 @@ impl CPU {
                     self.registers.special.lo,
                 );
 
+                // execute() may be handed instructions that never went
+                // through decode()
+                if res.op == AluOp::None {
+                    return Err(Exception::new(ExceptionCode::ReservedInstruction));
+                }
+                // Integer overflow leaves the destination untouched
+                if res.overflow {
+                    return Err(Exception::new(ExceptionCode::Overflow));
+                }
+                if res.trap {
+                    return Err(Exception::new(ExceptionCode::Trap));
+                }
+
                 if let Some(hi) = res.hi {
                     self.registers.special.hi = hi;
                 }
@@ -127,11 +840,12 @@ impl CPU {
                     self.registers.special.lo = lo;
                 }
 
-                if r.rd != 0 {
+                // Traps carry a code in the rd bits; MOVZ/MOVN may not write
+                if r.rd != 0 && res.write_rd {
                     self.registers.write(r.rd as usize, res.value);
                 }
 
-                res.value
+                Ok(res.value)
             }
 
             Instruction::IType(i) => {
@@ -143,162 +857,268 @@ impl CPU {
 
                 // === Branch instructions ===
                 match i.opcode {
-                    0x04 => {
-                        // BEQ
-                        if rs_val == rt_val {
-                            let offset = ((i.imm as i16 as i32) << 2) as u32;
-                            let delay = self.fetch(bus);
-                            if let Some(v) = delay {
-                                let delay_decoded = self.decode(v);
-                                self.execute(bus, delay_decoded);
-                            }
-                            self.registers.special.pc = pc_next.wrapping_add(offset);
-                        }
-                        return 0;
-                    }
-                    0x05 => {
-                        // BNE
-                        if rs_val != rt_val {
-                            let offset = ((i.imm as i16 as i32) << 2) as u32;
-                            let delay = self.fetch(bus);
-                            if let Some(v) = delay {
-                                let delay_decoded = self.decode(v);
-                                self.execute(bus, delay_decoded);
-                            }
-                            self.registers.special.pc = pc_next.wrapping_add(offset);
+                    0x04..=0x07 => {
+                        // BEQ / BNE / BLEZ / BGTZ
+                        let taken = match i.opcode {
+                            0x04 => rs_val == rt_val,
+                            0x05 => rs_val != rt_val,
+                            0x06 => (rs_val as i32) <= 0,
+                            _ => (rs_val as i32) > 0,
+                        };
+                        return self.branch(taken, pc_next, imm_u << 2);
+                    }
+                    0x14..=0x17 => {
+                        // BEQL / BNEL / BLEZL / BGTZL
+                        let taken = match i.opcode {
+                            0x14 => rs_val == rt_val,
+                            0x15 => rs_val != rt_val,
+                            0x16 => (rs_val as i32) <= 0,
+                            _ => (rs_val as i32) > 0,
+                        };
+                        return self.branch_likely(taken, pc_next, imm_u << 2);
+                    }
+                    0x01 if matches!(i.rt, 0x02 | 0x03 | 0x12 | 0x13) => {
+                        // BLTZL / BGEZL / BLTZALL / BGEZALL
+                        let taken = if i.rt & 0x1 == 0 {
+                            (rs_val as i32) < 0
+                        } else {
+                            (rs_val as i32) >= 0
+                        };
+                        if i.rt & 0x10 != 0 {
+                            // Link is written whether or not the branch is taken
+                            self.registers.write(31, pc_next.wrapping_add(4));
                         }
-                        return 0;
-                    }
-                    0x06 => {
-                        // BLEZ
-                        if (rs_val as i32) <= 0 {
-                            let offset = ((i.imm as i16 as i32) << 2) as u32;
-                            let delay = self.fetch(bus);
-                            if let Some(v) = delay {
-                                let delay_decoded = self.decode(v);
-                                self.execute(bus, delay_decoded);
-                            }
-                            self.registers.special.pc = pc_next.wrapping_add(offset);
+                        return self.branch_likely(taken, pc_next, imm_u << 2);
+                    }
+                    0x01 if matches!(i.rt, 0x08..=0x0C | 0x0E) => {
+                        // TGEI, TGEIU, TLTI, TLTIU, TEQI, TNEI: same
+                        // conditions as the SPECIAL traps (funct 0x30 | rt[2:0])
+                        let res = ALU::execute(
+                            rs_val,
+                            imm_u,
+                            0,
+                            0,
+                            0,
+                            0x30 | (i.rt & 0x7),
+                            false,
+                            self.registers.special.hi,
+                            self.registers.special.lo,
+                        );
+                        if res.trap {
+                            return Err(Exception::new(ExceptionCode::Trap));
                         }
-                        return 0;
-                    }
-                    0x07 => {
-                        // BGTZ
-                        if (rs_val as i32) > 0 {
-                            let offset = ((i.imm as i16 as i32) << 2) as u32;
-                            let delay = self.fetch(bus);
-                            if let Some(v) = delay {
-                                let delay_decoded = self.decode(v);
-                                self.execute(bus, delay_decoded);
-                            }
-                            self.registers.special.pc = pc_next.wrapping_add(offset);
+                        return Ok(0);
+                    }
+                    0x01 if i.rt == 0x1F => {
+                        // SYNCI offset(rs): no caches to synchronise
+                        if !self.is_r2() {
+                            return Err(Exception::new(ExceptionCode::ReservedInstruction));
                         }
-                        return 0;
+                        return Ok(0);
                     }
                     0x01 => {
-                        let rt = i.rt;
-                        let (cond, link) = match rt {
-                            0x00 => ((rs_val as i32) < 0, false),  // BLTZ
-                            0x01 => ((rs_val as i32) >= 0, false), // BGEZ
-                            0x10 => ((rs_val as i32) < 0, true),   // BLTZAL
-                            0x11 => ((rs_val as i32) >= 0, true),  // BGEZAL
-                            _ => (false, false),
+                        // BLTZ / BGEZ / BLTZAL / BGEZAL
+                        let taken = match i.rt {
+                            0x00 | 0x10 => (rs_val as i32) < 0,
+                            0x01 | 0x11 => (rs_val as i32) >= 0,
+                            _ => return Err(Exception::new(ExceptionCode::ReservedInstruction)),
                         };
-
-                        // delay slot SIEMPRE
-                        if let Some(v) = self.fetch(bus) {
-                            let d = self.decode(v);
-                            self.execute(bus, d);
-                        }
-
-                        if cond {
-                            if link {
-                                self.registers
-                                    .write(31, self.registers.get_pc().wrapping_add(4));
-                            } // PC+8
-                            let branch_target = self
-                                .registers
-                                .get_pc()
-                                .wrapping_add(((i.imm as i16 as i32) << 2) as u32);
-                            self.registers.special.pc = branch_target;
+                        if i.rt & 0x10 != 0 {
+                            // Link is written whether or not the branch is taken
+                            self.registers.write(31, pc_next.wrapping_add(4));
                         }
-                        return 0;
+                        return self.branch(taken, pc_next, imm_u << 2);
                     }
 
                     0x23 => {
                         // LW rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
-                        if let Ok(val) = bus.read32_virt(addr) {
-                            if i.rt != 0 {
-                                self.registers.write(i.rt as usize, val);
-                            }
-                            return val;
+                        let val = self.load32(bus, addr)?;
+                        if i.rt != 0 {
+                            self.registers.write(i.rt as usize, val);
+                        }
+                        return Ok(val);
+                    }
+                    0x30 => {
+                        // LL rt, offset(rs)
+                        let addr = rs_val.wrapping_add(imm_u);
+                        let val = self.load32(bus, addr)?;
+                        let paddr = self.translate(addr, MemAccess::Load)?;
+                        self.ll_bit = true;
+                        self.ll_addr = paddr;
+                        self.cop0.lladdr = paddr >> 4;
+                        bus.set_link(paddr);
+                        if i.rt != 0 {
+                            self.registers.write(i.rt as usize, val);
+                        }
+                        return Ok(val);
+                    }
+                    0x38 => {
+                        // SC rt, offset(rs)
+                        let addr = rs_val.wrapping_add(imm_u);
+                        MemoryBus::check_alignment(addr, 4, true)
+                            .map_err(|e| Exception::from_memory(e, MemAccess::Store))?;
+                        let paddr = self.translate(addr, MemAccess::Store)?;
+                        let success = self.ll_bit && paddr == self.ll_addr && bus.link_valid(paddr);
+                        if success {
+                            self.store32(bus, addr, rt_val)?;
+                        }
+                        self.ll_bit = false;
+                        let val = success as u32;
+                        if i.rt != 0 {
+                            self.registers.write(i.rt as usize, val);
                         }
-                        return 0;
+                        return Ok(val);
                     }
                     0x2B => {
                         // SW rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
                         let val = rt_val;
-                        bus.write32_virt(addr, val).ok();
-                        return 0;
+                        self.store32(bus, addr, val)?;
+                        return Ok(0);
                     }
                     0x20 => {
                         // LB rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
-                        let val = bus.read8_virt(addr).unwrap_or(0) as i8 as i32 as u32;
+                        let val = self.load8(bus, addr)? as i8 as i32 as u32;
                         if i.rt != 0 {
                             self.registers.write(i.rt as usize, val);
                         }
-                        return val;
+                        return Ok(val);
                     }
                     0x24 => {
                         // LBU rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
-                        let val = bus.read8_virt(addr).unwrap_or(0) as u32;
+                        let val = self.load8(bus, addr)? as u32;
                         if i.rt != 0 {
                             self.registers.write(i.rt as usize, val);
                         }
-                        return val;
+                        return Ok(val);
                     }
                     0x21 => {
                         // LH rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
-                        let val = bus.read16(addr).unwrap_or(0) as i16 as i32 as u32;
+                        let val = self.load16(bus, addr)? as i16 as i32 as u32;
                         if i.rt != 0 {
                             self.registers.write(i.rt as usize, val);
                         }
-                        return val;
+                        return Ok(val);
                     }
                     0x25 => {
                         // LHU rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
-                        let val = bus.read16(addr).unwrap_or(0) as u32;
+                        let val = self.load16(bus, addr)? as u32;
                         if i.rt != 0 {
                             self.registers.write(i.rt as usize, val);
                         }
-                        return val;
+                        return Ok(val);
+                    }
+                    0x22 | 0x26 => {
+                        // LWL / LWR rt, offset(rs)
+                        // Faults report the effective address, not the word
+                        let addr = rs_val.wrapping_add(imm_u);
+                        let paddr = self.data_paddr(addr, 1, MemAccess::Load)?;
+                        let mem = bus
+                            .read32(paddr & !3)
+                            .map_err(|e| Exception::from_memory(e, MemAccess::Load))?;
+                        let lane = Self::byte_lane(addr, bus.little_endian);
+                        let val = if i.opcode == 0x22 {
+                            (rt_val & (0x00FF_FFFF >> lane)) | (mem << (24 - lane))
+                        } else {
+                            (rt_val & !(0xFFFF_FFFF >> lane)) | (mem >> lane)
+                        };
+                        if i.rt != 0 {
+                            self.registers.write(i.rt as usize, val);
+                        }
+                        return Ok(val);
+                    }
+                    0x2A | 0x2E => {
+                        // SWL / SWR rt, offset(rs)
+                        // Translated as a store, and only the affected bytes
+                        // are written: the word is never read back
+                        let addr = rs_val.wrapping_add(imm_u);
+                        let paddr = self.data_paddr(addr, 1, MemAccess::Store)?;
+                        let lane = Self::byte_lane(addr, bus.little_endian);
+                        let (mask, val) = if i.opcode == 0x2A {
+                            (0xFFFF_FFFFu32 >> (24 - lane), rt_val >> (24 - lane))
+                        } else {
+                            (0xFFFF_FFFFu32 << lane, rt_val << lane)
+                        };
+                        for k in 0..4 {
+                            if mask & (0xFF << (k * 8)) == 0 {
+                                continue;
+                            }
+                            let off = if bus.little_endian { k } else { 3 - k };
+                            bus.write8((paddr & !3) + off, (val >> (k * 8)) as u8)
+                                .map_err(|e| Exception::from_memory(e, MemAccess::Store))?;
+                        }
+                        return Ok(0);
                     }
+                    0x31 => {
+                        // LWC1 ft, offset(rs)
+                        self.cop1_usable()?;
+                        let addr = rs_val.wrapping_add(imm_u);
+                        let val = self.load32(bus, addr)?;
+                        self.cop1.write_w(i.rt as usize, val);
+                        return Ok(val);
+                    }
+                    0x39 => {
+                        // SWC1 ft, offset(rs)
+                        self.cop1_usable()?;
+                        let addr = rs_val.wrapping_add(imm_u);
+                        let val = self.cop1.read_w(i.rt as usize);
+                        self.store32(bus, addr, val)?;
+                        return Ok(0);
+                    }
+                    0x35 => {
+                        // LDC1 ft, offset(rs)
+                        self.cop1_usable()?;
+                        let addr = rs_val.wrapping_add(imm_u);
+                        self.load_fpr64(bus, addr, i.rt as usize)?;
+                        return Ok(0);
+                    }
+                    0x3D => {
+                        // SDC1 ft, offset(rs)
+                        self.cop1_usable()?;
+                        let addr = rs_val.wrapping_add(imm_u);
+                        self.store_fpr64(bus, addr, i.rt as usize)?;
+                        return Ok(0);
+                    }
+                    0x13 => return self.execute_cop1x(bus, i),
                     0x28 => {
                         // SB rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
                         let val = (rt_val & 0xFF) as u8;
-                        bus.write8_virt(addr, val).ok();
-                        return 0;
+                        self.store8(bus, addr, val)?;
+                        return Ok(0);
                     }
                     0x29 => {
                         // SH rt, offset(rs)
                         let addr = rs_val.wrapping_add(imm_u);
                         let val = (rt_val & 0xFFFF) as u16;
-                        bus.write16(addr, val).ok();
-                        return 0;
+                        self.store16(bus, addr, val)?;
+                        return Ok(0);
+                    }
+                    0x2F => {
+                        // CACHE op, offset(rs): privileged, no caches modelled
+                        if !self.cop0.is_kernel_mode() && !self.cop0.status.cu(0) {
+                            return Err(Exception::coprocessor_unusable(0));
+                        }
+                        return Ok(0);
+                    }
+                    0x33 => {
+                        // PREF hint, offset(rs): a hint only
+                        return Ok(0);
+                    }
+                    0x12 | 0x32 | 0x36 | 0x3A | 0x3E => {
+                        // COP2, LWC2, LDC2, SWC2, SDC2: no coprocessor 2
+                        return Err(Exception::coprocessor_unusable(2));
                     }
                     _ => {}
                 }
 
                 // Normal I-Type ALU ops
 
-                // println!("Inmediate: 0x{:04X} (signed: {})", i.imm, imm_signed);
                 let res = ALU::execute(
                     rs_val,
                     rt_val,
@@ -311,11 +1131,18 @@ impl CPU {
                     self.registers.special.lo,
                 );
 
+                if res.op == AluOp::None {
+                    return Err(Exception::new(ExceptionCode::ReservedInstruction));
+                }
+                if res.overflow {
+                    return Err(Exception::new(ExceptionCode::Overflow));
+                }
+
                 if i.rt != 0 {
                     self.registers.write(i.rt as usize, res.value);
                 }
 
-                res.value
+                Ok(res.value)
             }
 
             Instruction::Special2(s) => match s.funct {
@@ -329,7 +1156,7 @@ impl CPU {
                     let res = acc.wrapping_add(prod);
                     self.registers.special.hi = (res >> 32) as u32;
                     self.registers.special.lo = (res & 0xFFFF_FFFF) as u32;
-                    0
+                    Ok(0)
                 }
 
                 0x01 => {
@@ -342,7 +1169,7 @@ impl CPU {
                     let res = acc.wrapping_add(prod);
                     self.registers.special.hi = (res >> 32) as u32;
                     self.registers.special.lo = (res & 0xFFFF_FFFF) as u32;
-                    0
+                    Ok(0)
                 }
 
                 0x02 => {
@@ -350,11 +1177,9 @@ impl CPU {
                     let rs_val = self.registers.read(s.rs as usize) as i32 as i64;
                     let rt_val = self.registers.read(s.rt as usize) as i32 as i64;
                     let prod = rs_val.wrapping_mul(rt_val);
-                    let rd = s.rd as usize;
-                    self.registers.write(rd, prod as u32);
-                    self.registers.special.hi = (prod >> 32) as u32;
-                    self.registers.special.lo = (prod & 0xFFFF_FFFF) as u32;
-                    0
+                    // Only rd is written; HI/LO keep their contents
+                    self.registers.write(s.rd as usize, prod as u32);
+                    Ok(prod as u32)
                 }
 
                 0x04 => {
@@ -367,7 +1192,7 @@ impl CPU {
                     let res = acc.wrapping_sub(prod);
                     self.registers.special.hi = (res >> 32) as u32;
                     self.registers.special.lo = (res & 0xFFFF_FFFF) as u32;
-                    0
+                    Ok(0)
                 }
 
                 0x05 => {
@@ -380,17 +1205,28 @@ impl CPU {
                     let res = acc.wrapping_sub(prod);
                     self.registers.special.hi = (res >> 32) as u32;
                     self.registers.special.lo = (res & 0xFFFF_FFFF) as u32;
-                    0
+                    Ok(0)
                 }
 
-                _ => {
-                    println!(
-                        "[SPECIAL2] Unhandled funct=0x{:02X} at PC={:#010X}",
-                        s.funct,
-                        self.registers.get_pc()
-                    );
-                    0
+                0x20 | 0x21 => {
+                    // CLZ / CLO rd, rs
+                    let rs_val = self.registers.read(s.rs as usize);
+                    let val = if s.funct == 0x20 {
+                        rs_val.leading_zeros()
+                    } else {
+                        rs_val.leading_ones()
+                    };
+                    self.registers.write(s.rd as usize, val);
+                    Ok(val)
                 }
+
+                0x3F => {
+                    // SDBBP code
+                    let code = (s.encode() >> 6) & 0xF_FFFF;
+                    self.host_trap(bus, HostTrap::Sdbbp, code)
+                }
+
+                _ => Err(Exception::new(ExceptionCode::ReservedInstruction)),
             },
 
             Instruction::JType(j) => {
@@ -401,16 +1237,17 @@ impl CPU {
                     self.registers.write(31, pc_next.wrapping_add(4));
                 }
 
-                if let Some(v) = self.fetch(bus) {
-                    let delay_decoded = self.decode(v);
-                    self.execute(bus, delay_decoded);
-                }
-
-                self.registers.special.pc = target;
-                0
+                self.branch_to(target);
+                Ok(0)
             }
 
-            _ => 0,
+            Instruction::Special3(s) => self.execute_special3(s),
+
+            Instruction::Cop0(c) => self.execute_cop0(c),
+
+            Instruction::Cop1(c) => self.execute_cop1(c),
+
+            Instruction::Invalid(_) => Err(Exception::new(ExceptionCode::ReservedInstruction)),
         }
     }
 
@@ -420,3 +1257,897 @@ impl CPU {
 
     pub fn writeback(&mut self, _mem_result: u32) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::{ram::Ram, rom::Rom};
+
+    const RESET: u32 = 0xBFC0_0000;
+    /// General exception vector while Status.BEV is set.
+    const BEV_GENERAL: u32 = 0xBFC0_0380;
+
+    /// `model` with 64 KiB of RAM at 0 and `prog` at the reset vector.
+    fn boot_model(model: CpuModel, prog: &[u32], little_endian: bool) -> (CPU, MemoryBus) {
+        let mut bus = MemoryBus::new(little_endian);
+        bus.add_device(Box::new(Ram::new(0, 0x1_0000)));
+        let mut rom = vec![0u8; 0x1000];
+        for (n, w) in prog.iter().enumerate() {
+            let b = if little_endian {
+                w.to_le_bytes()
+            } else {
+                w.to_be_bytes()
+            };
+            rom[n * 4..n * 4 + 4].copy_from_slice(&b);
+        }
+        bus.add_device(Box::new(Rom::new(0x1FC0_0000, rom)));
+        (CPU::with_model(model), bus)
+    }
+
+    fn boot(prog: &[u32]) -> (CPU, MemoryBus) {
+        boot_model(CpuModel::default(), prog, true)
+    }
+
+    fn run(cpu: &mut CPU, bus: &mut MemoryBus, steps: usize) {
+        for _ in 0..steps {
+            cpu.step(bus);
+        }
+    }
+
+    // ---- Encoders ----
+
+    fn i(op: u32, rs: u32, rt: u32, imm: u32) -> u32 {
+        (op << 26) | (rs << 21) | (rt << 16) | (imm & 0xFFFF)
+    }
+
+    fn r(op: u32, rs: u32, rt: u32, rd: u32, sa: u32, funct: u32) -> u32 {
+        (op << 26) | (rs << 21) | (rt << 16) | (rd << 11) | (sa << 6) | funct
+    }
+
+    fn lui(rt: u32, imm: u32) -> u32 {
+        i(0x0F, 0, rt, imm)
+    }
+
+    fn ori(rt: u32, rs: u32, imm: u32) -> u32 {
+        i(0x0D, rs, rt, imm)
+    }
+
+    fn mtc0(rt: u32, rd: u32, sel: u32) -> u32 {
+        r(0x10, 0x04, rt, rd, 0, sel)
+    }
+
+    fn mtc1(rt: u32, fs: u32) -> u32 {
+        r(0x11, 0x04, rt, fs, 0, 0)
+    }
+
+    fn mfc1(rt: u32, fs: u32) -> u32 {
+        r(0x11, 0x00, rt, fs, 0, 0)
+    }
+
+    fn ctc1(rt: u32, fs: u32) -> u32 {
+        r(0x11, 0x06, rt, fs, 0, 0)
+    }
+
+    fn cfc1(rt: u32, fs: u32) -> u32 {
+        r(0x11, 0x02, rt, fs, 0, 0)
+    }
+
+    /// COP1 computational op `fmt.funct fd, fs, ft`.
+    fn fp(fmt: u32, funct: u32, fd: u32, fs: u32, ft: u32) -> u32 {
+        r(0x11, fmt, ft, fs, fd, funct)
+    }
+
+    /// Status = CU1 | BEV | ERL (KUSEG unmapped), via r8.
+    fn fpu_prelude() -> Vec<u32> {
+        vec![lui(8, 0x2040), ori(8, 8, 0x0004), mtc0(8, 12, 0)]
+    }
+
+    fn run_program(prog: &[u32]) -> (CPU, MemoryBus) {
+        let (mut cpu, mut bus) = boot(prog);
+        run(&mut cpu, &mut bus, prog.len());
+        (cpu, bus)
+    }
+
+    // ---- FPU ----
+
+    #[test]
+    fn fpu_arith_convert_compare_branch() {
+        let mut p = fpu_prelude();
+        p.extend([
+            ori(1, 0, 3),
+            mtc1(1, 0),
+            fp(0x14, 0x20, 2, 0, 0),    // cvt.s.w f2, f0
+            fp(0x10, 0x00, 4, 2, 2),    // add.s f4, f2, f2
+            fp(0x10, 0x24, 6, 4, 0),    // cvt.w.s f6, f4
+            mfc1(3, 6),
+            fp(0x10, 0x3C, 0, 2, 4),    // c.lt.s f2, f4 -> cc0 = 1
+            i(0x11, 0x08, 1, 2),        // bc1t +2
+            0,                          // delay slot
+            ori(5, 0, 1),               // skipped
+            ori(6, 0, 1),
+        ]);
+        // One instruction is branched over
+        let (mut cpu, mut bus) = boot(&p);
+        run(&mut cpu, &mut bus, p.len() - 1);
+        assert_eq!(cpu.registers.read(3), 6);
+        assert_eq!(cpu.registers.read(5), 0);
+        assert_eq!(cpu.registers.read(6), 1);
+    }
+
+    #[test]
+    fn fpu_doubleword_load_store() {
+        let mut p = fpu_prelude();
+        p.extend([
+            i(0x35, 0, 2, 0x200),    // ldc1 f2, 0x200
+            fp(0x11, 0x00, 4, 2, 2), // add.d f4, f2, f2
+            i(0x3D, 0, 4, 0x208),    // sdc1 f4, 0x208
+            mfc1(3, 5),              // high word (FR = 0 pair)
+        ]);
+        let (mut cpu, mut bus) = boot(&p);
+        bus.write32(0x204, 0x3FF8_0000).unwrap(); // 1.5
+        run(&mut cpu, &mut bus, p.len());
+        assert_eq!(bus.read32(0x208).unwrap(), 0);
+        assert_eq!(bus.read32(0x20C).unwrap(), 0x4008_0000); // 3.0
+        assert_eq!(cpu.registers.read(3), 0x4008_0000);
+    }
+
+    #[test]
+    fn fpu_rounding_mode_from_fcsr() {
+        let div_third = |rm: u32| {
+            let mut p = fpu_prelude();
+            p.extend([
+                ori(9, 0, rm),
+                ctc1(9, 31),
+                ori(1, 0, 1),
+                mtc1(1, 0),
+                ori(1, 0, 3),
+                mtc1(1, 2),
+                fp(0x14, 0x20, 0, 0, 0), // cvt.s.w f0, f0
+                fp(0x14, 0x20, 2, 2, 0), // cvt.s.w f2, f2
+                fp(0x10, 0x03, 4, 0, 2), // div.s f4, f0, f2
+                mfc1(3, 4),
+                cfc1(5, 31),
+            ]);
+            let (cpu, _) = run_program(&p);
+            (cpu.registers.read(3), cpu.registers.read(5))
+        };
+        let (rn, fcsr) = div_third(0);
+        assert_eq!(rn, (1.0f32 / 3.0).to_bits()); // rounded up
+        assert_eq!([1, 2, 3].map(|rm| div_third(rm).0), [rn - 1, rn, rn - 1]);
+        assert_eq!(fcsr & ((1 << 12) | (1 << 2)), (1 << 12) | (1 << 2)); // Cause.I, Flags.I
+    }
+
+    #[test]
+    fn fpu_enabled_invalid_traps() {
+        let mut p = fpu_prelude();
+        p.extend([
+            ori(9, 0, 1 << 11),
+            ctc1(9, 31),             // Enable.V
+            fp(0x10, 0x03, 4, 0, 0), // div.s f4, f0, f0 (0 / 0)
+        ]);
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), BEV_GENERAL);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::FloatingPoint as u32);
+        assert_eq!(cpu.cop0.epc, RESET + 5 * 4);
+        assert_ne!(cpu.cop1.fcsr() & (1 << 16), 0); // Cause.V
+    }
+
+    #[test]
+    fn fpu_ctc1_with_enabled_cause_traps() {
+        let mut p = fpu_prelude();
+        p.extend([lui(9, 0x0001), ori(9, 9, 0x0800), ctc1(9, 31)]); // Cause.V | Enable.V
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::FloatingPoint as u32);
+        assert_eq!(cpu.cop1.fcsr(), 0x0001_0800);
+    }
+
+    #[test]
+    fn fpu_moves_keep_signaling_nan_bits() {
+        let mut p = fpu_prelude();
+        p.extend([
+            lui(1, 0x7FC0),
+            ori(1, 1, 0x0001), // sNaN in the legacy encoding
+            mtc1(1, 3),
+            mfc1(2, 3),
+            fp(0x10, 0x06, 5, 3, 0), // mov.s f5, f3
+            mfc1(4, 5),
+        ]);
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.read(2), 0x7FC0_0001);
+        assert_eq!(cpu.registers.read(4), 0x7FC0_0001);
+    }
+
+    // ---- Branch delay slots ----
+
+    const ERET: u32 = 0x4200_0018;
+
+    /// `words` at the reset vector, `handler` at the BEV general vector.
+    fn with_handler(words: &[u32], handler: &[u32]) -> Vec<u32> {
+        let mut p = vec![0; 0x380 / 4 + handler.len()];
+        p[..words.len()].copy_from_slice(words);
+        p[0x380 / 4..].copy_from_slice(handler);
+        p
+    }
+
+    #[test]
+    fn exception_in_delay_slot_reports_the_branch() {
+        let p = [
+            ori(1, 0, 1),            // 00
+            i(0x01, 0, 0x11, 3),     // 04 bgezal r0, +3 -> 0x14
+            ori(2, 0, 2),            // 08 delay slot
+            ori(3, 0, 3),            // 0C skipped
+            ori(4, 0, 4),            // 10 skipped
+            i(0x04, 0, 0, 4),        // 14 beq r0, r0, +4
+            i(0x23, 0, 5, 2),        // 18 lw r5, 2(r0): AdEL in the delay slot
+        ];
+        let (mut cpu, mut bus) = boot(&p);
+        run(&mut cpu, &mut bus, 3);
+        assert_eq!(cpu.registers.get_pc(), RESET + 0x14);
+        assert_eq!(cpu.registers.read(31), RESET + 0x0C);
+        assert_eq!(cpu.registers.read(2), 2);
+        assert_eq!(cpu.registers.read(3), 0);
+
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::AddressErrorLoad as u32);
+        assert!(cpu.cop0.cause.bd());
+        assert_eq!(cpu.cop0.epc, RESET + 0x14);
+        assert_eq!(cpu.cop0.badvaddr, 2);
+        assert_eq!(cpu.registers.get_pc(), BEV_GENERAL);
+        assert_eq!(cpu.pending_branch, None);
+    }
+
+    #[test]
+    fn interrupt_while_branch_pending_reruns_the_branch() {
+        use crate::devices::intc::{INTC_MASK, InterruptController};
+
+        let p = with_handler(
+            &[
+                lui(8, 0x0040),
+                ori(8, 8, 0x0401),  // BEV | IM2 | IE
+                mtc0(8, 12, 0),
+                i(0x04, 0, 0, 2),   // 0C beq r0, r0, +2 -> 0x18
+                ori(2, 0, 2),       // 10 delay slot
+                ori(3, 0, 3),       // 14 skipped
+                ori(4, 0, 4),       // 18 target
+            ],
+            &[ERET],
+        );
+        let (mut cpu, mut bus) = boot(&p);
+        let intc = InterruptController::new(0x1F00_0000);
+        let lines = intc.lines();
+        bus.add_device(Box::new(intc));
+        bus.write32(0x1F00_0000 + INTC_MASK, 1).unwrap();
+        cpu.connect_irq(lines.clone());
+
+        run(&mut cpu, &mut bus, 4);
+        assert_eq!(cpu.pending_branch, Some(RESET + 0x18));
+
+        // The request lands between the branch and its delay slot
+        lines.raise(0);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::Interrupt as u32);
+        assert!(cpu.cop0.cause.bd());
+        assert_eq!(cpu.cop0.epc, RESET + 0x0C);
+        assert_eq!(cpu.registers.get_pc(), BEV_GENERAL);
+        assert_eq!(cpu.pending_branch, None);
+        assert_eq!(cpu.registers.read(2), 0);
+
+        // ERET returns to the branch, which runs its delay slot again
+        lines.lower(0);
+        run(&mut cpu, &mut bus, 3);
+        assert_eq!(cpu.registers.read(2), 2);
+        assert_eq!(cpu.registers.read(3), 0);
+        assert_eq!(cpu.registers.get_pc(), RESET + 0x18);
+    }
+
+    #[test]
+    fn jump_and_branch_links() {
+        let mut p = vec![0; 0x70 / 4];
+        p[0] = (0x03 << 26) | (((RESET + 0x40) >> 2) & 0x03FF_FFFF); // jal 0x40
+        p[0x40 / 4] = lui(4, RESET >> 16);
+        p[0x44 / 4] = ori(4, 4, 0x60);
+        p[0x48 / 4] = r(0, 4, 0, 5, 0, 0x09); // jalr r5, r4
+        p[0x60 / 4] = i(0x01, 0, 0x10, 4); // bltzal r0, +4: not taken, still links
+        p[0x68 / 4] = ori(6, 0, 6);
+        let (mut cpu, mut bus) = boot(&p);
+
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(cpu.registers.read(31), RESET + 0x08);
+        assert_eq!(cpu.registers.get_pc(), RESET + 0x40);
+
+        run(&mut cpu, &mut bus, 4);
+        assert_eq!(cpu.registers.read(5), RESET + 0x50);
+        assert_eq!(cpu.registers.get_pc(), RESET + 0x60);
+
+        run(&mut cpu, &mut bus, 3);
+        assert_eq!(cpu.registers.read(31), RESET + 0x68);
+        assert_eq!(cpu.registers.read(6), 6);
+    }
+
+    // ---- TLB ----
+
+    #[test]
+    fn tlb_mapped_access_and_faults() {
+        let p = [
+            lui(8, 0x0040),
+            mtc0(8, 12, 0),           // Status = BEV: ERL off, KUSEG mapped
+            lui(3, 0x0040),
+            mtc0(3, 10, 0),           // EntryHi = 0x00400000, ASID 0
+            ori(4, 0, 0x86),
+            mtc0(4, 2, 0),            // EntryLo0 = PFN 2, V | D
+            mtc0(0, 3, 0),            // EntryLo1 = 0 (invalid)
+            mtc0(0, 0, 0),            // Index = 0
+            r(0x10, 0x10, 0, 0, 0, 2), // tlbwi
+            ori(5, 0, 0xBEEF),
+            i(0x2B, 3, 5, 4),         // sw r5, 4(r3)
+            i(0x23, 3, 6, 4),         // lw r6, 4(r3)
+            i(0x2B, 3, 5, 0x1000),    // sw to the odd page: TLB Invalid (store)
+        ];
+        let (cpu, mut bus) = run_program(&p);
+        assert_eq!(bus.read32(0x2004).unwrap(), 0xBEEF);
+        assert_eq!(cpu.registers.read(6), 0xBEEF);
+        assert_eq!(cpu.registers.get_pc(), BEV_GENERAL);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::TlbStore as u32);
+        assert_eq!(cpu.cop0.badvaddr, 0x0040_1000);
+    }
+
+    #[test]
+    fn tlb_miss_takes_the_refill_vector() {
+        let p = [lui(8, 0x0040), mtc0(8, 12, 0), lui(3, 0x0080), i(0x23, 3, 6, 0)];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), 0xBFC0_0200);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::TlbLoad as u32);
+        assert_eq!(cpu.cop0.badvaddr, 0x0080_0000);
+        assert_eq!(cpu.cop0.entry_hi, 0x0080_0000);
+        assert_eq!(cpu.cop0.context, 0x0080_0000 >> 9);
+    }
+
+    // ---- Exception vectors ----
+
+    #[test]
+    fn bev_clear_uses_ebase_vectors() {
+        let p = [mtc0(0, 12, 0), i(0x23, 0, 5, 2)]; // Status = 0; lw r5, 2(r0)
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), 0x8000_0180);
+        assert_eq!(cpu.cop0.epc, RESET + 4);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::AddressErrorLoad as u32);
+        assert!(cpu.cop0.status.exl());
+    }
+
+    #[test]
+    fn moved_ebase_relocates_general_and_refill_vectors() {
+        let p = [
+            lui(8, 0x8001),
+            mtc0(8, 15, 1),        // EBase = 0x80010000
+            mtc0(0, 12, 0),        // Status = 0
+            lui(3, 0x0080),
+            i(0x23, 3, 5, 0),      // lw from unmapped KUSEG: TLB refill
+        ];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), 0x8001_0000);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::TlbLoad as u32);
+
+        let p = [lui(8, 0x8001), mtc0(8, 15, 1), mtc0(0, 12, 0), i(0x23, 0, 5, 2)];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), 0x8001_0180);
+    }
+
+    #[test]
+    fn bev_refill_uses_bootstrap_vector() {
+        // Status = BEV only: ERL clear so KUSEG goes through the TLB
+        let p = [lui(8, 0x0040), mtc0(8, 12, 0), lui(3, 0x0080), i(0x23, 3, 5, 0)];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), 0xBFC0_0200);
+    }
+
+    #[test]
+    fn nested_exception_keeps_epc_and_skips_refill_vector() {
+        let p = [mtc0(0, 12, 0), i(0x23, 0, 5, 2)];
+        let (mut cpu, mut bus) = boot(&p);
+        // General handler faults again: lw r5, 2(r0), then a KUSEG TLB miss
+        bus.write32(0x180, i(0x23, 0, 5, 2)).unwrap();
+        run(&mut cpu, &mut bus, 3);
+        assert_eq!(cpu.registers.get_pc(), 0x8000_0180);
+        assert_eq!(cpu.cop0.epc, RESET + 4);
+        assert_eq!(cpu.cop0.badvaddr, 2);
+
+        bus.write32(0x180, i(0x23, 0, 5, 0x100)).unwrap();
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.registers.get_pc(), 0x8000_0180);
+        assert_eq!(cpu.cop0.epc, RESET + 4);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::TlbLoad as u32);
+    }
+
+    // ---- Unaligned loads and stores ----
+
+    #[test]
+    fn unaligned_word_round_trip() {
+        for le in [true, false] {
+            // The "low" instruction takes the least significant bytes
+            let (sw_lo, sw_hi) = if le { (0x2E, 0x2A) } else { (0x2A, 0x2E) };
+            let (lw_lo, lw_hi) = if le { (0x26, 0x22) } else { (0x22, 0x26) };
+            let p = [
+                lui(1, 0x1122),
+                ori(1, 1, 0x3344),
+                i(sw_lo, 0, 1, 1),
+                i(sw_hi, 0, 1, 4),
+                i(lw_lo, 0, 2, 1),
+                i(lw_hi, 0, 2, 4),
+            ];
+            let (mut cpu, mut bus) = boot_model(CpuModel::default(), &p, le);
+            run(&mut cpu, &mut bus, p.len());
+            let bytes: Vec<u8> = (1..5).map(|a| bus.read8(a).unwrap()).collect();
+            let expected = if le {
+                [0x44, 0x33, 0x22, 0x11]
+            } else {
+                [0x11, 0x22, 0x33, 0x44]
+            };
+            assert_eq!(bytes, expected, "little_endian = {}", le);
+            assert_eq!(cpu.registers.read(2), 0x1122_3344);
+        }
+    }
+
+    #[test]
+    fn unaligned_accesses_fault_by_direction() {
+        // KUSEG is mapped once ERL is cleared: SWL/SWR miss as stores
+        for (op, code) in [
+            (0x2A, ExceptionCode::TlbStore),
+            (0x2E, ExceptionCode::TlbStore),
+            (0x22, ExceptionCode::TlbLoad),
+            (0x26, ExceptionCode::TlbLoad),
+        ] {
+            let p = [lui(1, 0x0040), mtc0(1, 12, 0), i(op, 0, 2, 0x1235)];
+            let (cpu, _) = run_program(&p);
+            assert_eq!(cpu.cop0.cause.exc_code(), code as u32, "op {:02X}", op);
+            assert_eq!(cpu.cop0.badvaddr, 0x1235);
+        }
+    }
+
+    // ---- Alignment ----
+
+    #[test]
+    fn misaligned_accesses_raise_address_errors() {
+        let (cpu, _) = run_program(&[i(0x23, 0, 2, 2)]); // lw r2, 2(r0)
+        assert_eq!(cpu.registers.get_pc(), BEV_GENERAL);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::AddressErrorLoad as u32);
+        assert_eq!(cpu.cop0.badvaddr, 2);
+        assert_eq!(cpu.cop0.epc, RESET);
+
+        let (cpu, mut bus) = run_program(&[i(0x29, 0, 2, 1)]); // sh r2, 1(r0)
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::AddressErrorStore as u32);
+        assert_eq!(cpu.cop0.badvaddr, 1);
+        assert_eq!(bus.read32(0).unwrap(), 0);
+    }
+
+    // ---- LL/SC ----
+
+    #[test]
+    fn ll_sc_success_and_broken_links() {
+        let p = [
+            ori(3, 0, 0x55),
+            i(0x30, 0, 1, 0x100), // ll r1, 0x100(r0)
+            i(0x38, 0, 3, 0x100), // sc r3 -> 1
+            ori(4, 0, 0x66),
+            i(0x30, 0, 1, 0x100),
+            i(0x2B, 0, 0, 0x100), // sw clears the link
+            i(0x38, 0, 4, 0x100), // sc r4 -> 0
+        ];
+        let (cpu, mut bus) = run_program(&p);
+        assert_eq!(cpu.registers.read(3), 1);
+        assert_eq!(cpu.registers.read(4), 0);
+        assert_eq!(bus.read32(0x100).unwrap(), 0);
+        assert_eq!(cpu.cop0.lladdr, 0x100 >> 4);
+    }
+
+    #[test]
+    fn eret_clears_llbit() {
+        let p = [
+            lui(8, 0xBFC0),
+            ori(8, 8, 0x0018),
+            mtc0(8, 30, 0),       // ErrorEPC = RESET + 0x18
+            lui(9, 0x8000),       // KSEG0: still unmapped once ERL drops
+            i(0x30, 9, 1, 0x100), // ll
+            ERET,
+            i(0x38, 9, 5, 0x100), // sc r5 -> 0
+        ];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), RESET + 0x1C);
+        assert!(!cpu.ll_bit);
+        assert_eq!(cpu.registers.read(5), 0);
+    }
+
+    // ---- Privilege segments ----
+
+    #[test]
+    fn user_mode_cannot_fetch_from_kseg1() {
+        // Status = BEV | KSU=user; the next fetch from KSEG1 faults
+        let p = [lui(8, 0x0040), ori(8, 8, 0x10), mtc0(8, 12, 0), 0];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::AddressErrorLoad as u32);
+        assert_eq!(cpu.cop0.badvaddr, RESET + 0x0C);
+        assert_eq!(cpu.cop0.epc, RESET + 0x0C);
+        assert_eq!(cpu.registers.get_pc(), BEV_GENERAL);
+    }
+
+    #[test]
+    fn segments_follow_privilege_mode() {
+        let mut cpu = CPU::new();
+        let ksu = |mode: u32| STATUS_BEV | (mode << STATUS_KSU_SHIFT);
+        let code = |r: Result<u32, Exception>| r.map_err(|e| e.code);
+
+        // Kernel: KSEG0/KSEG1 unmapped, KSEG2 through the (empty) TLB
+        cpu.cop0.status.0 = ksu(0);
+        assert_eq!(cpu.translate(0x8000_0100, MemAccess::Store), Ok(0x100));
+        assert_eq!(cpu.translate(0xA000_0100, MemAccess::Load), Ok(0x100));
+        assert_eq!(
+            code(cpu.translate(0xC000_0100, MemAccess::Store)),
+            Err(ExceptionCode::TlbStore)
+        );
+
+        // Supervisor: SSEG goes to the TLB, KSEG0 and KSEG3 are address errors
+        cpu.cop0.status.0 = ksu(1);
+        assert_eq!(
+            code(cpu.translate(0xC000_0100, MemAccess::Load)),
+            Err(ExceptionCode::TlbLoad)
+        );
+        assert_eq!(
+            code(cpu.translate(0x8000_0100, MemAccess::Store)),
+            Err(ExceptionCode::AddressErrorStore)
+        );
+        assert_eq!(
+            code(cpu.translate(0xE000_0100, MemAccess::Fetch)),
+            Err(ExceptionCode::AddressErrorLoad)
+        );
+
+        // User: only KUSEG; EXL raises the privilege back to kernel
+        cpu.cop0.status.0 = ksu(2);
+        let err = cpu.translate(0xC000_0100, MemAccess::Load).unwrap_err();
+        assert_eq!(err.code, ExceptionCode::AddressErrorLoad);
+        assert_eq!(err.bad_vaddr, Some(0xC000_0100));
+        assert_eq!(
+            code(cpu.translate(0x0000_0100, MemAccess::Load)),
+            Err(ExceptionCode::TlbLoad)
+        );
+        cpu.cop0.status.0 = ksu(2) | STATUS_EXL;
+        assert_eq!(cpu.translate(0x8000_0100, MemAccess::Load), Ok(0x100));
+    }
+
+    // ---- SPECIAL3 ----
+
+    fn special3(rs: u32, rt: u32, rd: u32, sa: u32, funct: u32) -> u32 {
+        r(0x1F, rs, rt, rd, sa, funct)
+    }
+
+    #[test]
+    fn special3_bit_field_and_byte_ops() {
+        let p = [
+            lui(1, 0x1234),
+            ori(1, 1, 0x5680),
+            special3(1, 2, 7, 4, 0x00),    // ext r2, r1, 4, 8 -> 0x68
+            ori(3, 0, 0xFFFF),
+            special3(0, 3, 11, 4, 0x04),   // ins r3, r0, 4, 8 -> 0xF00F
+            special3(0, 1, 4, 0x10, 0x20), // seb r4, r1
+            special3(0, 1, 5, 0x02, 0x20), // wsbh r5, r1
+            r(0, 1, 1, 6, 8, 0x02),        // rotr r6, r1, 8
+            ori(7, 0, 0xBEEF),
+            mtc0(7, 4, 2),                 // UserLocal
+            special3(0, 8, 29, 0, 0x3B),   // rdhwr r8, $29
+        ];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.read(2), 0x68);
+        assert_eq!(cpu.registers.read(3), 0xF00F);
+        assert_eq!(cpu.registers.read(4), 0xFFFF_FF80);
+        assert_eq!(cpu.registers.read(5), 0x3412_8056);
+        assert_eq!(cpu.registers.read(6), 0x8012_3456);
+        assert_eq!(cpu.registers.read(8), 0xBEEF);
+    }
+
+    #[test]
+    fn special3_is_reserved_on_release_1() {
+        let p = [special3(1, 2, 7, 4, 0x00)];
+        let (mut cpu, mut bus) = boot_model(CpuModel::M4KC, &p, true);
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::ReservedInstruction as u32);
+        assert_eq!(cpu.cop0.epc, RESET);
+    }
+
+    // ---- Conditional moves ----
+
+    #[test]
+    fn conditional_moves() {
+        let mut p = fpu_prelude();
+        p.extend([
+            ori(1, 0, 7),
+            ori(2, 0, 9),
+            r(0, 1, 0, 3, 0, 0x0A),  // movz r3, r1, r0 -> 7
+            r(0, 2, 0, 4, 0, 0x0B),  // movn r4, r2, r0 -> untouched
+            r(0, 2, 1, 5, 0, 0x0B),  // movn r5, r2, r1 -> 9
+            r(0, 1, 1, 6, 0, 0x01),  // movt r6, r1, cc0 (clear) -> untouched
+            r(0, 1, 0, 7, 0, 0x01),  // movf r7, r1, cc0 -> 7
+            mtc1(1, 2),
+            fp(0x10, 0x32, 0, 2, 2), // c.eq.s f2, f2 -> cc0 = 1
+            r(0, 2, 1, 9, 0, 0x01),  // movt r9, r2, cc0 -> 9
+            fp(0x10, 0x11, 4, 2, 1), // movt.s f4, f2, cc0
+            fp(0x10, 0x13, 6, 2, 0), // movn.s f6, f2, r0 -> untouched
+            mfc1(10, 4),
+            mfc1(11, 6),
+        ]);
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.read(3), 7);
+        assert_eq!(cpu.registers.read(4), 0);
+        assert_eq!(cpu.registers.read(5), 9);
+        assert_eq!(cpu.registers.read(6), 0);
+        assert_eq!(cpu.registers.read(7), 7);
+        assert_eq!(cpu.registers.read(9), 9);
+        assert_eq!(cpu.registers.read(10), 7);
+        assert_eq!(cpu.registers.read(11), 0);
+    }
+
+    // ---- Branch likely ----
+
+    #[test]
+    fn branch_likely_annuls_untaken_delay_slots() {
+        let p = [
+            ori(1, 0, 1),          // 00
+            i(0x14, 1, 0, 2),      // 04 beql r1, r0 (not taken)
+            ori(2, 0, 5),          // 08 annulled
+            i(0x15, 1, 0, 2),      // 0C bnel r1, r0 (taken -> 0x18)
+            ori(3, 0, 6),          // 10 delay slot runs
+            ori(4, 0, 7),          // 14 skipped
+            i(0x01, 1, 0x12, 2),   // 18 bltzall r1 (not taken, still links)
+            ori(5, 0, 8),          // 1C annulled
+            ori(6, 0, 9),          // 20
+        ];
+        let (mut cpu, mut bus) = boot(&p);
+        run(&mut cpu, &mut bus, 6);
+        assert_eq!(cpu.registers.read(2), 0);
+        assert_eq!(cpu.registers.read(3), 6);
+        assert_eq!(cpu.registers.read(4), 0);
+        assert_eq!(cpu.registers.read(5), 0);
+        assert_eq!(cpu.registers.read(6), 9);
+        assert_eq!(cpu.registers.read(31), RESET + 0x20);
+    }
+
+    #[test]
+    fn fpu_branch_likely() {
+        let mut p = fpu_prelude();
+        p.extend([
+            i(0x11, 0x08, 2, 2), // bc1fl +2 (cc0 clear: taken)
+            ori(2, 0, 1),        // delay slot runs
+            ori(3, 0, 1),        // skipped
+            i(0x11, 0x08, 3, 2), // bc1tl +2 (not taken)
+            ori(4, 0, 1),        // annulled
+            ori(5, 0, 1),
+        ]);
+        let (mut cpu, mut bus) = boot(&p);
+        run(&mut cpu, &mut bus, 3 + 4);
+        assert_eq!(cpu.registers.read(2), 1);
+        assert_eq!(cpu.registers.read(3), 0);
+        assert_eq!(cpu.registers.read(4), 0);
+        assert_eq!(cpu.registers.read(5), 1);
+    }
+
+    // ---- Undefined encodings ----
+
+    #[test]
+    fn undefined_encodings_raise_reserved_instruction() {
+        for w in [
+            0xFC00_0000u32,            // opcode 0x3F
+            r(0, 1, 2, 3, 0, 0x05),    // SPECIAL funct 0x05
+            r(0x1C, 0, 0, 0, 0, 0x10), // SPECIAL2 funct 0x10
+            i(0x01, 0, 0x04, 0),       // REGIMM rt 0x04
+            fp(0x10, 0x20, 0, 0, 0),   // cvt.s.s
+        ] {
+            assert!(matches!(Instruction::decode(w), Instruction::Invalid(_)), "{:08X}", w);
+            let (cpu, _) = run_program(&[ori(5, 0, 7), w]);
+            assert_eq!(
+                cpu.cop0.cause.exc_code(),
+                ExceptionCode::ReservedInstruction as u32,
+                "{:08X}",
+                w
+            );
+            assert_eq!(cpu.cop0.epc, RESET + 4);
+        }
+    }
+
+    #[test]
+    fn execute_rejects_undefined_alu_functs() {
+        // Bypasses decode: the executor must not fall through silently
+        let (mut cpu, mut bus) = run_program(&[ori(3, 0, 9)]);
+        let instr = Instruction::RType(RType {
+            opcode: 0,
+            rs: 1,
+            rt: 2,
+            rd: 3,
+            shamt: 0,
+            funct: 0x05,
+        });
+        let err = cpu.execute(&mut bus, instr).unwrap_err();
+        assert_eq!(err.code, ExceptionCode::ReservedInstruction);
+        assert_eq!(cpu.registers.read(3), 9);
+    }
+
+    #[test]
+    fn hints_and_cache_ops_are_no_ops_in_kernel_mode() {
+        let p = [
+            r(0, 0, 0, 0, 0, 0x0F), // sync
+            i(0x33, 0, 1, 0),       // pref
+            i(0x2F, 0, 1, 0),       // cache
+            ori(5, 0, 7),
+        ];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.read(5), 7);
+        assert_eq!(cpu.registers.read(1), 0);
+        assert!(!cpu.cop0.status.exl());
+
+        let (cpu, _) = run_program(&[i(0x32, 0, 1, 0)]); // lwc2
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::CoprocessorUnusable as u32);
+        assert_eq!(cpu.cop0.cause.ce(), 2);
+    }
+
+    #[test]
+    fn cop1x_and_shadow_gpr_moves() {
+        let madd_s = r(0x13, 4, 2, 0, 6, 0x20); // madd.s f6, f4, f0, f2
+        let lwxc1 = r(0x13, 1, 2, 0, 8, 0x00); // lwxc1 f8, r2(r1)
+        let rdpgpr = r(0x10, 0x0A, 3, 7, 0, 0); // rdpgpr r7, r3
+        for w in [madd_s, lwxc1, rdpgpr] {
+            assert!(!matches!(Instruction::decode(w), Instruction::Invalid(_)), "{:08X}", w);
+        }
+        let mut p = fpu_prelude();
+        p.extend([
+            ori(1, 0, 2),
+            mtc1(1, 0),
+            fp(0x14, 0x20, 0, 0, 0), // f0 = 2.0
+            ori(1, 0, 3),
+            mtc1(1, 2),
+            fp(0x14, 0x20, 2, 2, 0), // f2 = 3.0
+            ori(1, 0, 1),
+            mtc1(1, 4),
+            fp(0x14, 0x20, 4, 4, 0), // f4 = 1.0
+            madd_s,
+            mfc1(5, 6),
+            lui(9, 0x1234),
+            i(0x2B, 0, 9, 0x104),
+            ori(1, 0, 0x100),
+            ori(2, 0, 4),
+            lwxc1,
+            mfc1(6, 8),
+            ori(3, 0, 77),
+            rdpgpr,
+        ]);
+        let (cpu, _) = run_program(&p);
+        assert_eq!(f32::from_bits(cpu.registers.read(5)), 7.0);
+        assert_eq!(cpu.registers.read(6), 0x1234_0000);
+        assert_eq!(cpu.registers.read(7), 77);
+
+        // Release 1 has neither
+        for w in [madd_s, rdpgpr] {
+            let mut p = fpu_prelude();
+            p.push(w);
+            let (mut cpu, mut bus) = boot_model(CpuModel::M4KC, &p, true);
+            run(&mut cpu, &mut bus, p.len());
+            assert_eq!(
+                cpu.cop0.cause.exc_code(),
+                ExceptionCode::ReservedInstruction as u32,
+                "{:08X}",
+                w
+            );
+        }
+    }
+
+    // ---- CPU models ----
+
+    #[test]
+    fn model_identification_registers() {
+        let cpu = CPU::with_model(CpuModel::M24KF);
+        let config1 = 0x8000_0000 | (31 << 25) | (0xA3 << 16) | (0xA3 << 7) | 1;
+        assert_eq!(cpu.cop0.read(16, 1), config1);
+        assert_eq!(cpu.cop0.read(15, 0), 0x0001_9300);
+        assert_eq!(cpu.cop0.read(16, 0) & (7 << 10), 1 << 10);
+    }
+
+    #[test]
+    fn release_1_gates_cu1_and_di() {
+        let p = [lui(8, 0x2040), mtc0(8, 12, 0), r(0x10, 0x0B, 0, 12, 0, 0)]; // di
+        let (mut cpu, mut bus) = boot_model(CpuModel::M4KC, &p, true);
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(cpu.cop0.status.0 & STATUS_CU1, 0);
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.cop0.cause.exc_code(), ExceptionCode::ReservedInstruction as u32);
+    }
+
+    #[test]
+    fn release_1_has_no_ebase_hwrena_or_userlocal() {
+        let p = [
+            lui(8, 0x8001),
+            mtc0(8, 15, 1), // EBase
+            ori(9, 0, 0xF),
+            mtc0(9, 7, 0),  // HWREna
+            mtc0(8, 4, 2),  // UserLocal
+            mtc0(0, 12, 0), // Status = 0
+            i(0x23, 0, 5, 2),
+        ];
+        let (mut cpu, mut bus) = boot_model(CpuModel::M4KC, &p, true);
+        run(&mut cpu, &mut bus, p.len());
+        assert_eq!(cpu.cop0.read(15, 1), 0x8000_0000);
+        assert_eq!(cpu.cop0.read(7, 0), 0);
+        assert_eq!(cpu.cop0.read(4, 2), 0);
+        assert_eq!(cpu.registers.get_pc(), 0x8000_0180);
+
+        // The same program on an R2 core moves the vector
+        let (mut cpu, mut bus) = boot_model(CpuModel::M4KEC, &p, true);
+        run(&mut cpu, &mut bus, p.len());
+        assert_eq!(cpu.cop0.read(7, 0), 0xF);
+        assert_eq!(cpu.cop0.read(4, 2), 0x8001_0000);
+        assert_eq!(cpu.registers.get_pc(), 0x8001_0180);
+    }
+
+    #[test]
+    fn fir_and_status_fr_follow_the_model() {
+        // Status = CU1 | FR | BEV | ERL, then read FIR and Status back
+        let p = [
+            lui(8, 0x2440),
+            ori(8, 8, 0x0004),
+            mtc0(8, 12, 0),
+            cfc1(3, 0),
+            r(0x10, 0x00, 4, 12, 0, 0), // mfc0 r4, Status
+        ];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.read(3), CpuModel::M24KF.fir());
+        assert_ne!(cpu.registers.read(3) & FIR_F64, 0);
+        assert_ne!(cpu.registers.read(4) & STATUS_FR, 0);
+        assert!(cpu.cop1.fr);
+
+        let mut r1_fpu = CpuModel::M24KF;
+        r1_fpu.isa = IsaRevision::R1;
+        let (mut cpu, mut bus) = boot_model(r1_fpu, &p, true);
+        run(&mut cpu, &mut bus, p.len());
+        assert_eq!(cpu.registers.read(3) & (FIR_F64 | FIR_L), 0);
+        assert_eq!(cpu.registers.read(4) & STATUS_FR, 0);
+        assert_ne!(cpu.registers.read(4) & STATUS_CU1, 0);
+    }
+
+    // ---- SPECIAL2 and SDBBP ----
+
+    const SDBBP: u32 = 0x7000_003F;
+
+    #[test]
+    fn clz_clo_and_mul() {
+        let p = [
+            lui(1, 0x00F0),
+            r(0x1C, 1, 0, 2, 0, 0x20), // clz r2, r1 -> 8
+            lui(3, 0xFFF0),
+            r(0x1C, 3, 0, 4, 0, 0x21), // clo r4, r3 -> 12
+            r(0, 1, 0, 0, 0, 0x11),    // mthi r1
+            r(0x1C, 1, 1, 5, 0, 0x02), // mul r5, r1, r1: HI untouched
+        ];
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.read(2), 8);
+        assert_eq!(cpu.registers.read(4), 12);
+        assert_eq!(cpu.registers.read(5), 0);
+        assert_eq!(cpu.registers.special.hi, 0x00F0_0000);
+    }
+
+    #[test]
+    fn sdbbp_enters_debug_mode() {
+        let (cpu, _) = run_program(&[ori(1, 0, 1), SDBBP]);
+        assert_eq!(cpu.registers.get_pc(), DEBUG_VECTOR);
+        assert_eq!(cpu.cop0.depc, RESET + 4);
+        assert!(cpu.cop0.in_debug_mode());
+        assert_eq!(cpu.cop0.debug & DEBUG_DBD, 0);
+        // Debug exceptions leave Cause, EPC and Status alone
+        assert_eq!(cpu.cop0.cause.0, 0);
+        assert_eq!(cpu.cop0.epc, 0);
+        assert!(!cpu.cop0.status.exl());
+    }
+
+    #[test]
+    fn sdbbp_in_delay_slot_reports_the_branch() {
+        let p = [i(0x04, 0, 0, 4), SDBBP]; // beq r0, r0, +4
+        let (cpu, _) = run_program(&p);
+        assert_eq!(cpu.registers.get_pc(), DEBUG_VECTOR);
+        assert_eq!(cpu.cop0.depc, RESET);
+        assert_ne!(cpu.cop0.debug & DEBUG_DBD, 0);
+        assert_eq!(cpu.pending_branch, None);
+    }
+}
@@ -604,6 +604,10 @@ impl Device for GpuMmio {
         self.base..=self.base + 0xFF
     }
 
+    fn is_mmio(&self) -> bool {
+        true
+    }
+
     fn read8(&mut self, paddr: u32) -> MemResult<u8> {
         self.within(paddr)
             .map_or(Err(MemoryError::Unmapped(paddr)), |off| {
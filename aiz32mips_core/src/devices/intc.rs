@@ -0,0 +1,174 @@
+use crate::memory::{Device, MemResult, MemoryError};
+use core::cell::Cell;
+use core::ops::RangeInclusive;
+use std::rc::Rc;
+
+// Register offsets (all 32-bit)
+pub const INTC_PENDING: u32 = 0x00; // R: latched IRQ lines
+pub const INTC_MASK: u32 = 0x04; // R/W: enabled IRQ lines
+pub const INTC_ACK: u32 = 0x08; // W: write 1 to clear a pending line
+
+#[derive(Default)]
+struct IrqState {
+    pending: Cell<u32>,
+    mask: Cell<u32>,
+}
+
+/// Shared handle to the controller's 32 IRQ lines. Devices keep a clone and
+/// call `raise` / `lower`; the CPU polls `asserted` to drive Cause.IP2.
+#[derive(Clone, Default)]
+pub struct IrqLines {
+    state: Rc<IrqState>,
+}
+
+impl IrqLines {
+    /// Bit for `line`. Only lines 0-31 exist; anything else maps to no bit,
+    /// so raising or lowering it is a no-op.
+    #[inline]
+    fn bit(line: u32) -> u32 {
+        1u32.checked_shl(line).unwrap_or(0)
+    }
+
+    /// Latches `line` as pending until the guest acknowledges it.
+    pub fn raise(&self, line: u32) {
+        let st = &self.state;
+        st.pending.set(st.pending.get() | Self::bit(line));
+    }
+
+    /// Drops a pending `line` without guest acknowledgement.
+    pub fn lower(&self, line: u32) {
+        let st = &self.state;
+        st.pending.set(st.pending.get() & !Self::bit(line));
+    }
+
+    pub fn pending(&self) -> u32 {
+        self.state.pending.get()
+    }
+
+    pub fn mask(&self) -> u32 {
+        self.state.mask.get()
+    }
+
+    /// True when any unmasked line is pending.
+    pub fn asserted(&self) -> bool {
+        self.pending() & self.mask() != 0
+    }
+}
+
+pub struct InterruptController {
+    base: u32,
+    lines: IrqLines,
+}
+
+impl InterruptController {
+    pub fn new(base: u32) -> Self {
+        Self {
+            base,
+            lines: IrqLines::default(),
+        }
+    }
+
+    pub fn lines(&self) -> IrqLines {
+        self.lines.clone()
+    }
+
+    #[inline]
+    fn within(&self, paddr: u32) -> Option<u32> {
+        let off = paddr.wrapping_sub(self.base);
+        if off < 0x10 { Some(off) } else { None }
+    }
+}
+
+impl Device for InterruptController {
+    fn range(&self) -> RangeInclusive<u32> {
+        self.base..=self.base + 0x0F
+    }
+
+    fn is_mmio(&self) -> bool {
+        true
+    }
+
+    fn read8(&mut self, paddr: u32) -> MemResult<u8> {
+        let Some(off) = self.within(paddr) else {
+            return Err(MemoryError::Unmapped(paddr));
+        };
+
+        let shift = (off & 3) * 8;
+        let reg = match off & !3 {
+            INTC_PENDING => self.lines.pending(),
+            INTC_MASK => self.lines.mask(),
+            _ => 0,
+        };
+        Ok(((reg >> shift) & 0xFF) as u8)
+    }
+
+    fn write8(&mut self, paddr: u32, value: u8) -> MemResult<()> {
+        let Some(off) = self.within(paddr) else {
+            return Err(MemoryError::Unmapped(paddr));
+        };
+
+        let shift = (off & 3) * 8;
+        let bits = (value as u32) << shift;
+        let st = &self.lines.state;
+        match off & !3 {
+            INTC_MASK => {
+                let mask = !(0xFFu32 << shift);
+                st.mask.set((st.mask.get() & mask) | bits);
+            }
+            INTC_ACK => st.pending.set(st.pending.get() & !bits),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryBus;
+
+    #[test]
+    fn raise_lower_and_ack() {
+        let mut intc = InterruptController::new(0x1F00_0000);
+        let lines = intc.lines();
+        lines.raise(0);
+        lines.raise(31);
+        assert_eq!(lines.pending(), 0x8000_0001);
+        assert!(!lines.asserted());
+
+        intc.write8(0x1F00_0000 + INTC_MASK + 3, 0x80).unwrap();
+        assert!(lines.asserted());
+        intc.write8(0x1F00_0000 + INTC_ACK + 3, 0x80).unwrap();
+        assert_eq!(lines.pending(), 1);
+        lines.lower(0);
+        assert_eq!(lines.pending(), 0);
+    }
+
+    #[test]
+    fn out_of_range_lines_are_ignored() {
+        let lines = InterruptController::new(0).lines();
+        for line in [32, 33, 63, u32::MAX] {
+            lines.raise(line);
+        }
+        assert_eq!(lines.pending(), 0);
+        lines.raise(1);
+        lines.lower(33);
+        assert_eq!(lines.pending(), 2);
+    }
+
+    #[test]
+    fn word_access_is_endian_neutral() {
+        for little_endian in [true, false] {
+            let mut bus = MemoryBus::new(little_endian);
+            let intc = InterruptController::new(0x1F00_0000);
+            let lines = intc.lines();
+            bus.add_device(Box::new(intc));
+            bus.write32(0x1F00_0000 + INTC_MASK, 1).unwrap();
+            assert_eq!(lines.mask(), 1, "little_endian = {}", little_endian);
+            lines.raise(0);
+            assert_eq!(bus.read32(0x1F00_0000 + INTC_PENDING).unwrap(), 1);
+            bus.write32(0x1F00_0000 + INTC_ACK, 1).unwrap();
+            assert_eq!(lines.pending(), 0);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod gpu;
+pub mod intc;
 pub mod ram;
 pub mod rom;
 pub mod vram;
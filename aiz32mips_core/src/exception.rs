@@ -0,0 +1,105 @@
+use crate::memory::MemoryError;
+use crate::tlb::TlbFault;
+
+/// Cause.ExcCode values (MIPS32 encoding).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExceptionCode {
+    Interrupt = 0,
+    TlbModified = 1,
+    TlbLoad = 2,
+    TlbStore = 3,
+    AddressErrorLoad = 4,
+    AddressErrorStore = 5,
+    BusErrorFetch = 6,
+    BusErrorData = 7,
+    Syscall = 8,
+    Breakpoint = 9,
+    ReservedInstruction = 10,
+    CoprocessorUnusable = 11,
+    Overflow = 12,
+    Trap = 13,
+    FloatingPoint = 15,
+}
+
+/// Kind of memory access that produced a `MemoryError`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemAccess {
+    Fetch,
+    Load,
+    Store,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Exception {
+    pub code: ExceptionCode,
+    pub bad_vaddr: Option<u32>,
+    /// Coprocessor number reported in Cause.CE.
+    pub coprocessor: u8,
+    /// TLB miss taken through the refill vector (offset 0x000).
+    pub refill: bool,
+}
+
+impl Exception {
+    pub fn new(code: ExceptionCode) -> Self {
+        Self {
+            code,
+            bad_vaddr: None,
+            coprocessor: 0,
+            refill: false,
+        }
+    }
+
+    pub fn coprocessor_unusable(unit: u8) -> Self {
+        Self {
+            code: ExceptionCode::CoprocessorUnusable,
+            bad_vaddr: None,
+            coprocessor: unit,
+            refill: false,
+        }
+    }
+
+    pub fn with_bad_vaddr(code: ExceptionCode, vaddr: u32) -> Self {
+        Self {
+            code,
+            bad_vaddr: Some(vaddr),
+            coprocessor: 0,
+            refill: false,
+        }
+    }
+
+    pub fn from_tlb(fault: TlbFault, vaddr: u32, access: MemAccess) -> Self {
+        let code = match (fault, access) {
+            (TlbFault::Modified, _) => ExceptionCode::TlbModified,
+            (_, MemAccess::Store) => ExceptionCode::TlbStore,
+            _ => ExceptionCode::TlbLoad,
+        };
+        Self {
+            refill: fault == TlbFault::Refill,
+            ..Self::with_bad_vaddr(code, vaddr)
+        }
+    }
+
+    /// TLB Refill / Invalid / Modified: the handler also expects Context
+    /// and EntryHi to describe the faulting page.
+    pub fn is_tlb(&self) -> bool {
+        matches!(
+            self.code,
+            ExceptionCode::TlbModified | ExceptionCode::TlbLoad | ExceptionCode::TlbStore
+        )
+    }
+
+    pub fn from_memory(err: MemoryError, access: MemAccess) -> Self {
+        match err {
+            MemoryError::AddressErrorLoad(addr) => {
+                Self::with_bad_vaddr(ExceptionCode::AddressErrorLoad, addr)
+            }
+            MemoryError::AddressErrorStore(addr) => {
+                Self::with_bad_vaddr(ExceptionCode::AddressErrorStore, addr)
+            }
+            MemoryError::Unmapped(_) | MemoryError::RomWrite(_) => match access {
+                MemAccess::Fetch => Self::new(ExceptionCode::BusErrorFetch),
+                _ => Self::new(ExceptionCode::BusErrorData),
+            },
+        }
+    }
+}
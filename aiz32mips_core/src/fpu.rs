@@ -0,0 +1,830 @@
+use crate::cop::{Cop1, FCSR_RM_MASK, FIR_F64, FIR_L};
+use crate::exception::{Exception, ExceptionCode};
+use crate::instructions::Cop1Ins;
+use core::cmp::Ordering;
+
+// COP1 fmt field
+pub const FMT_S: u8 = 0x10;
+pub const FMT_D: u8 = 0x11;
+pub const FMT_W: u8 = 0x14;
+pub const FMT_L: u8 = 0x15;
+
+// IEEE exception bits, as laid out in the FCSR Flags / Enables / Cause fields
+pub const FPE_INEXACT: u32 = 1 << 0;
+pub const FPE_UNDERFLOW: u32 = 1 << 1;
+pub const FPE_OVERFLOW: u32 = 1 << 2;
+pub const FPE_DIV_ZERO: u32 = 1 << 3;
+pub const FPE_INVALID: u32 = 1 << 4;
+pub const FPE_UNIMPLEMENTED: u32 = 1 << 5; // Cause only, cannot be masked
+
+const FLAGS_SHIFT: u32 = 2;
+const ENABLES_SHIFT: u32 = 7;
+const CAUSE_SHIFT: u32 = 12;
+
+// 2^108: lifts subnormal products/quotients back into the normal range
+const TINY_SCALE: f64 = 324_518_553_658_426_726_783_156_020_576_256.0;
+
+// MIPS legacy NaN encoding: the quiet bit set means *signaling*
+pub const DEFAULT_NAN_S: u32 = 0x7FBF_FFFF;
+pub const DEFAULT_NAN_D: u64 = 0x7FF7_FFFF_FFFF_FFFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    Nearest,
+    Zero,
+    Up,
+    Down,
+}
+
+impl RoundingMode {
+    pub fn from_fcsr(fcsr: u32) -> Self {
+        match fcsr & FCSR_RM_MASK {
+            0 => RoundingMode::Nearest,
+            1 => RoundingMode::Zero,
+            2 => RoundingMode::Up,
+            _ => RoundingMode::Down,
+        }
+    }
+}
+
+/// Result of an FPU operation, committed only if it does not trap.
+#[derive(Clone, Copy, Debug)]
+enum FpValue {
+    S(f32),
+    D(f64),
+    W(u32),
+    L(u64),
+    Cond(bool),
+}
+
+pub struct FPU;
+
+impl FPU {
+    /// Executes a COP1 computational instruction (fmt = S, D, W or L),
+    /// updating FCSR Cause/Flags and raising FPE for enabled exceptions.
+    ///
+    /// The L format, the .L conversions, RECIP and RSQRT need a 64-bit FPU
+    /// (FIR.F64 / FIR.L) and raise RI otherwise.
+    pub fn execute(cop1: &mut Cop1, ins: Cop1Ins) -> Result<(), Exception> {
+        let rm = RoundingMode::from_fcsr(cop1.fcsr());
+        let (value, raised) = Self::compute(cop1, ins, rm)?;
+        Self::commit(cop1, value, raised, ins.fd)
+    }
+
+    /// COP1X MADD / MSUB / NMADD / NMSUB.fmt fd, fr, fs, ft (`funct` bits
+    /// 2:0 select S or D). Release 2 is unfused: the product is rounded to
+    /// the format before `fr` is added, and the N forms negate the result.
+    pub fn execute_madd(
+        cop1: &mut Cop1,
+        funct: u8,
+        fr: u8,
+        fs: u8,
+        ft: u8,
+        fd: u8,
+    ) -> Result<(), Exception> {
+        let single = match funct & 0x7 {
+            0 => true,
+            1 => false,
+            _ => return Err(Exception::new(ExceptionCode::ReservedInstruction)),
+        };
+        let rm = RoundingMode::from_fcsr(cop1.fcsr());
+        let (fr, fs, ft) = (fr as usize, fs as usize, ft as usize);
+
+        let a = Self::read(cop1, single, fs);
+        let b = Self::read(cop1, single, ft);
+        let c = Self::read(cop1, single, fr);
+        let snan = |i| Self::is_snan(cop1, single, i);
+        let any_snan = snan(fs) || snan(ft) || snan(fr);
+
+        let (prod, mut raised) = Self::arith(0x02, single, a, b, any_snan, rm);
+        let p = Self::value_f64(prod);
+        let mut value = prod;
+        if !p.is_nan() {
+            let add_sub = if funct & 0x08 != 0 { 0x01 } else { 0x00 };
+            let (sum, flags) = Self::arith(add_sub, single, p, c, snan(fr), rm);
+            raised |= flags;
+            value = match sum {
+                FpValue::S(v) if funct & 0x10 != 0 => FpValue::S(-v),
+                FpValue::D(v) if funct & 0x10 != 0 => FpValue::D(-v),
+                other => other,
+            };
+        }
+        Self::commit(cop1, value, raised, fd)
+    }
+
+    /// Latches Cause, then either traps (enabled exception: Flags and the
+    /// destination are left untouched) or accumulates Flags and writes `fd`.
+    fn commit(cop1: &mut Cop1, value: FpValue, raised: u32, fd: u8) -> Result<(), Exception> {
+        let enables = (cop1.fcsr() >> ENABLES_SHIFT) & 0x1F;
+        let mut fcsr = cop1.fcsr() & !(0x3F << CAUSE_SHIFT);
+        fcsr |= raised << CAUSE_SHIFT;
+        if raised & (enables | FPE_UNIMPLEMENTED) != 0 {
+            // Trapping: Flags and the destination are left untouched
+            cop1.set_fcsr(fcsr);
+            return Err(Exception::new(ExceptionCode::FloatingPoint));
+        }
+        fcsr |= (raised & 0x1F) << FLAGS_SHIFT;
+        cop1.set_fcsr(fcsr);
+
+        let cc = fd >> 2;
+        let fd = fd as usize;
+        match value {
+            FpValue::S(v) => cop1.write_s(fd, v),
+            FpValue::D(v) => cop1.write_d(fd, v),
+            FpValue::W(v) => cop1.write_w(fd, v),
+            FpValue::L(v) => cop1.write_l(fd, v),
+            FpValue::Cond(c) => cop1.set_condition(cc, c),
+        }
+        Ok(())
+    }
+
+    fn compute(cop1: &Cop1, ins: Cop1Ins, rm: RoundingMode) -> Result<(FpValue, u32), Exception> {
+        let fs = ins.fs as usize;
+        let ft = ins.ft as usize;
+        let reserved = Exception::new(ExceptionCode::ReservedInstruction);
+        let fpu64 = cop1.fir() & FIR_F64 != 0;
+
+        if ins.fmt == FMT_L {
+            if cop1.fir() & FIR_L == 0 {
+                return Err(reserved);
+            }
+            // i64 -> f64 may round: err is the sign of the exact remainder
+            let l = cop1.read_l(fs) as i64;
+            let x = l as f64;
+            let err = (l as i128 - x as i128).cmp(&0);
+            return match ins.funct {
+                0x20 => Ok(Self::round_s(x, err, rm)), // CVT.S.L
+                0x21 => Ok(Self::round_d(x, err, rm)), // CVT.D.L
+                _ => Err(reserved),
+            };
+        }
+
+        if ins.fmt == FMT_W {
+            let w = cop1.read_w(fs) as i32 as f64;
+            return match ins.funct {
+                0x20 => Ok(Self::round_s(w, Ordering::Equal, rm)), // CVT.S.W
+                0x21 => Ok((FpValue::D(w), 0)),                    // CVT.D.W
+                _ => Err(reserved),
+            };
+        }
+
+        let fmt = ins.fmt;
+        if fmt != FMT_S && fmt != FMT_D {
+            return Err(reserved);
+        }
+        let single = fmt == FMT_S;
+        if !fpu64 && matches!(ins.funct, 0x08..=0x0B | 0x15 | 0x16 | 0x25) {
+            return Err(reserved);
+        }
+
+        // S operands are widened to f64 (exactly); results are rounded back
+        let a = Self::read(cop1, single, fs);
+        let a_snan = Self::is_snan(cop1, single, fs);
+
+        let res = match ins.funct {
+            // ---- Arithmetic ----
+            0x00..=0x03 => {
+                let b = Self::read(cop1, single, ft);
+                let any_snan = a_snan || Self::is_snan(cop1, single, ft);
+                Self::arith(ins.funct, single, a, b, any_snan, rm)
+            }
+            0x04 => {
+                // SQRT
+                if a.is_nan() {
+                    Self::nan_result(single, a_snan)
+                } else if a < 0.0 {
+                    Self::nan_result(single, true)
+                } else {
+                    let x = a.sqrt();
+                    let err = Self::sign((-x).mul_add(x, a));
+                    Self::round(single, x, err, rm)
+                }
+            }
+            0x05 | 0x07 => {
+                // ABS / NEG
+                if a.is_nan() {
+                    Self::nan_result(single, a_snan)
+                } else {
+                    let v = if ins.funct == 0x05 { a.abs() } else { -a };
+                    Self::round(single, v, Ordering::Equal, rm)
+                }
+            }
+            0x06 => {
+                // MOV: raw copy, no exceptions
+                if single {
+                    (FpValue::W(cop1.read_w(fs)), 0)
+                } else {
+                    (FpValue::L(cop1.read_l(fs)), 0)
+                }
+            }
+            0x15 => {
+                // RECIP
+                if a.is_nan() {
+                    Self::nan_result(single, a_snan)
+                } else {
+                    Self::arith(0x03, single, 1.0, a, false, rm)
+                }
+            }
+            0x16 => {
+                // RSQRT: the square root is rounded before dividing, within
+                // the accuracy the architecture allows for this instruction
+                if a.is_nan() {
+                    Self::nan_result(single, a_snan)
+                } else if a < 0.0 {
+                    Self::nan_result(single, true)
+                } else {
+                    Self::arith(0x03, single, 1.0, a.sqrt(), false, rm)
+                }
+            }
+            // ---- To long ----
+            0x08 => Self::to_long(a, RoundingMode::Nearest), // ROUND.L
+            0x09 => Self::to_long(a, RoundingMode::Zero),    // TRUNC.L
+            0x0A => Self::to_long(a, RoundingMode::Up),      // CEIL.L
+            0x0B => Self::to_long(a, RoundingMode::Down),    // FLOOR.L
+            0x25 => Self::to_long(a, rm),                    // CVT.L
+            // ---- To word ----
+            0x0C => Self::to_word(a, RoundingMode::Nearest), // ROUND.W
+            0x0D => Self::to_word(a, RoundingMode::Zero),    // TRUNC.W
+            0x0E => Self::to_word(a, RoundingMode::Up),      // CEIL.W
+            0x0F => Self::to_word(a, RoundingMode::Down),    // FLOOR.W
+            0x24 => Self::to_word(a, rm),                    // CVT.W
+            // ---- Conversions ----
+            0x20 if !single => {
+                // CVT.S.D
+                if a.is_nan() {
+                    Self::nan_result(true, a_snan)
+                } else {
+                    Self::round_s(a, Ordering::Equal, rm)
+                }
+            }
+            0x21 if single => {
+                // CVT.D.S
+                if a.is_nan() {
+                    Self::nan_result(false, a_snan)
+                } else {
+                    (FpValue::D(a), 0)
+                }
+            }
+            // ---- C.cond ----
+            0x30..=0x3F => {
+                let b = Self::read(cop1, single, ft);
+                let cond = ins.funct & 0xF;
+                let unordered = a.is_nan() || b.is_nan();
+                let res = (cond & 0x1 != 0 && unordered)
+                    || (cond & 0x2 != 0 && a == b)
+                    || (cond & 0x4 != 0 && a < b);
+                // cond bit 3 selects the signaling predicates
+                let invalid =
+                    (unordered && cond & 0x8 != 0) || a_snan || Self::is_snan(cop1, single, ft);
+                (FpValue::Cond(res), if invalid { FPE_INVALID } else { 0 })
+            }
+            _ => return Err(reserved),
+        };
+        Ok(res)
+    }
+
+    /// ADD / SUB / MUL / DIV with IEEE exception detection.
+    fn arith(
+        funct: u8,
+        single: bool,
+        a: f64,
+        b: f64,
+        any_snan: bool,
+        rm: RoundingMode,
+    ) -> (FpValue, u32) {
+        if a.is_nan() || b.is_nan() {
+            return Self::nan_result(single, any_snan);
+        }
+
+        // x is the round-to-nearest f64 result, err the sign of (exact - x)
+        let (x, err) = match funct {
+            0x00 | 0x01 => {
+                let b = if funct == 0x01 { -b } else { b };
+                let (x, err) = Self::two_sum(a, b);
+                // An exact zero sum is +0 except under round toward
+                // negative, where it is -0 unless both operands are +0
+                let both_pos_zero = a.to_bits() == 0 && b.to_bits() == 0;
+                if x == 0.0 && err == Ordering::Equal && rm == RoundingMode::Down && !both_pos_zero
+                {
+                    (-0.0, err)
+                } else {
+                    (x, err)
+                }
+            }
+            0x02 => {
+                let x = a * b;
+                if x == 0.0 && a != 0.0 && b != 0.0 {
+                    (x, Self::sign(a.signum() * b.signum()))
+                } else if x.abs() < f64::MIN_POSITIVE && x != 0.0 {
+                    (x, Self::tiny_mul_err(a, b, x))
+                } else {
+                    (x, Self::sign(a.mul_add(b, -x)))
+                }
+            }
+            _ => {
+                if b == 0.0 && a != 0.0 && a.is_finite() {
+                    let inf = if a.is_sign_negative() == b.is_sign_negative() {
+                        f64::INFINITY
+                    } else {
+                        f64::NEG_INFINITY
+                    };
+                    return (Self::exact(single, inf), FPE_DIV_ZERO);
+                }
+                let x = a / b;
+                if x == 0.0 && a != 0.0 && b.is_finite() {
+                    (x, Self::sign(a.signum() * b.signum()))
+                } else if x.abs() < f64::MIN_POSITIVE && x != 0.0 {
+                    (x, Self::tiny_div_err(a, b, x))
+                } else {
+                    let r = (-x).mul_add(b, a);
+                    let err = Self::sign(r);
+                    (x, if b < 0.0 { err.reverse() } else { err })
+                }
+            }
+        };
+
+        // inf - inf, 0 * inf, 0 / 0, inf / inf
+        if x.is_nan() {
+            return Self::nan_result(single, true);
+        }
+        if x.is_infinite() && a.is_finite() && b.is_finite() {
+            return Self::overflow_d(x.is_sign_positive(), rm);
+        }
+        Self::round(single, x, err, rm)
+    }
+
+    #[inline]
+    fn round(single: bool, x: f64, err: Ordering, rm: RoundingMode) -> (FpValue, u32) {
+        if single {
+            Self::round_s(x, err, rm)
+        } else {
+            Self::round_d(x, err, rm)
+        }
+    }
+
+    #[inline]
+    fn exact(single: bool, x: f64) -> FpValue {
+        if single {
+            FpValue::S(x as f32)
+        } else {
+            FpValue::D(x)
+        }
+    }
+
+    /// Rounds `x` (with `err` = sign of exact - x) to single precision.
+    fn round_s(x: f64, err: Ordering, rm: RoundingMode) -> (FpValue, u32) {
+        if x.is_infinite() {
+            return (FpValue::S(x as f32), 0);
+        }
+
+        let c = x as f32;
+        let cd = c as f64;
+        let above = match cd.partial_cmp(&x) {
+            Some(Ordering::Greater) => true,
+            Some(Ordering::Less) => false,
+            _ => match err {
+                Ordering::Equal => return (FpValue::S(c), 0),
+                Ordering::Greater => false,
+                Ordering::Less => true,
+            },
+        };
+
+        // lo < exact < hi are the neighbouring singles
+        let (lo, hi) = if above {
+            (c.next_down(), c)
+        } else {
+            (c, c.next_up())
+        };
+        let r = match rm {
+            RoundingMode::Nearest => {
+                // x sitting on a midpoint may hide which side the exact value is on
+                let mid = (lo as f64 + hi as f64) / 2.0;
+                if lo.is_finite() && hi.is_finite() && x == mid && err != Ordering::Equal {
+                    if err == Ordering::Greater { hi } else { lo }
+                } else {
+                    c
+                }
+            }
+            RoundingMode::Zero => {
+                if x > 0.0 {
+                    lo
+                } else {
+                    hi
+                }
+            }
+            RoundingMode::Up => hi,
+            RoundingMode::Down => lo,
+        };
+
+        let mut flags = FPE_INEXACT;
+        if r.is_infinite() || x.abs() >= 2f64.powi(128) {
+            flags |= FPE_OVERFLOW;
+        }
+        if x.abs() < f32::MIN_POSITIVE as f64 {
+            flags |= FPE_UNDERFLOW;
+        }
+        (FpValue::S(r), flags)
+    }
+
+    /// Applies the rounding mode to a round-to-nearest double result.
+    fn round_d(x: f64, err: Ordering, rm: RoundingMode) -> (FpValue, u32) {
+        if err == Ordering::Equal {
+            return (FpValue::D(x), 0);
+        }
+
+        let r = match rm {
+            RoundingMode::Nearest => x,
+            RoundingMode::Zero => {
+                if x > 0.0 && err == Ordering::Less {
+                    x.next_down()
+                } else if x < 0.0 && err == Ordering::Greater {
+                    x.next_up()
+                } else {
+                    x
+                }
+            }
+            RoundingMode::Up if err == Ordering::Greater => x.next_up(),
+            RoundingMode::Down if err == Ordering::Less => x.next_down(),
+            _ => x,
+        };
+
+        // Directed rounding can step from f64::MAX to infinity
+        let mut flags = FPE_INEXACT;
+        if r.is_infinite() {
+            flags |= FPE_OVERFLOW;
+        }
+        if x.abs() < f64::MIN_POSITIVE {
+            flags |= FPE_UNDERFLOW;
+        }
+        (FpValue::D(r), flags)
+    }
+
+    /// Double-precision overflow: infinity or the largest finite value,
+    /// depending on the rounding direction.
+    fn overflow_d(positive: bool, rm: RoundingMode) -> (FpValue, u32) {
+        let to_inf = match rm {
+            RoundingMode::Nearest => true,
+            RoundingMode::Zero => false,
+            RoundingMode::Up => positive,
+            RoundingMode::Down => !positive,
+        };
+        let mag = if to_inf { f64::INFINITY } else { f64::MAX };
+        let v = if positive { mag } else { -mag };
+        (FpValue::D(v), FPE_OVERFLOW | FPE_INEXACT)
+    }
+
+    #[inline]
+    fn round_integral(value: f64, rm: RoundingMode) -> f64 {
+        match rm {
+            RoundingMode::Nearest => value.round_ties_even(),
+            RoundingMode::Zero => value.trunc(),
+            RoundingMode::Up => value.ceil(),
+            RoundingMode::Down => value.floor(),
+        }
+    }
+
+    /// Float to long; NaN and out-of-range values give 2^63-1 and Invalid.
+    fn to_long(value: f64, rm: RoundingMode) -> (FpValue, u32) {
+        let r = Self::round_integral(value, rm);
+        if r.is_nan() || !(-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&r) {
+            return (FpValue::L(0x7FFF_FFFF_FFFF_FFFF), FPE_INVALID);
+        }
+        let flags = if r != value { FPE_INEXACT } else { 0 };
+        (FpValue::L(r as i64 as u64), flags)
+    }
+
+    /// Float to word; NaN and out-of-range values give 2^31-1 and Invalid.
+    fn to_word(value: f64, rm: RoundingMode) -> (FpValue, u32) {
+        let r = Self::round_integral(value, rm);
+        if r.is_nan() || !(-2_147_483_648.0..2_147_483_648.0).contains(&r) {
+            return (FpValue::W(0x7FFF_FFFF), FPE_INVALID);
+        }
+        let flags = if r != value { FPE_INEXACT } else { 0 };
+        (FpValue::W(r as i32 as u32), flags)
+    }
+
+    /// Default NaN result, signaling Invalid when requested.
+    fn nan_result(single: bool, invalid: bool) -> (FpValue, u32) {
+        let v = if single {
+            FpValue::W(DEFAULT_NAN_S)
+        } else {
+            FpValue::L(DEFAULT_NAN_D)
+        };
+        (v, if invalid { FPE_INVALID } else { 0 })
+    }
+
+    /// Numeric view of an S/D result (default NaNs included).
+    fn value_f64(v: FpValue) -> f64 {
+        match v {
+            FpValue::S(x) => x as f64,
+            FpValue::D(x) => x,
+            FpValue::W(bits) => f32::from_bits(bits) as f64,
+            FpValue::L(bits) => f64::from_bits(bits),
+            FpValue::Cond(_) => f64::NAN,
+        }
+    }
+
+    /// Sign of (a * b - x) for a subnormal product `x`, where the FMA
+    /// residual would itself underflow: the product is redone scaled into
+    /// the normal range. `p - x * 2^108` is exact there, and only when it
+    /// is zero does the (now exact) residual decide.
+    fn tiny_mul_err(a: f64, b: f64, x: f64) -> Ordering {
+        let (a, b) = if a.abs() < b.abs() {
+            (a * TINY_SCALE, b)
+        } else {
+            (a, b * TINY_SCALE)
+        };
+        let p = a * b;
+        let d = p - x * TINY_SCALE;
+        if d != 0.0 { Self::sign(d) } else { Self::sign(a.mul_add(b, -p)) }
+    }
+
+    /// Sign of (a / b - x) for a subnormal quotient `x`; see `tiny_mul_err`.
+    fn tiny_div_err(a: f64, b: f64, x: f64) -> Ordering {
+        let a = a * TINY_SCALE;
+        let q = a / b;
+        let d = q - x * TINY_SCALE;
+        if d != 0.0 {
+            return Self::sign(d);
+        }
+        let err = Self::sign((-q).mul_add(b, a));
+        if b < 0.0 { err.reverse() } else { err }
+    }
+
+    /// Knuth's TwoSum: the rounded sum and the sign of its error.
+    fn two_sum(a: f64, b: f64) -> (f64, Ordering) {
+        let x = a + b;
+        if !x.is_finite() {
+            return (x, Ordering::Equal);
+        }
+        let bb = x - a;
+        let e = (a - (x - bb)) + (b - bb);
+        (x, Self::sign(e))
+    }
+
+    #[inline]
+    fn sign(v: f64) -> Ordering {
+        v.partial_cmp(&0.0).unwrap_or(Ordering::Equal)
+    }
+
+    #[inline]
+    fn read(cop1: &Cop1, single: bool, index: usize) -> f64 {
+        if single {
+            cop1.read_s(index) as f64
+        } else {
+            cop1.read_d(index)
+        }
+    }
+
+    fn is_snan(cop1: &Cop1, single: bool, index: usize) -> bool {
+        if single {
+            let bits = cop1.read_w(index);
+            bits & 0x7F80_0000 == 0x7F80_0000 && bits & 0x0040_0000 != 0
+        } else {
+            let bits = cop1.read_l(index);
+            bits & 0x7FF0_0000_0000_0000 == 0x7FF0_0000_0000_0000
+                && bits & 0x0008_0000_0000_0000 != 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cop::FIR_DEFAULT;
+
+    // FCSR.RM values, in the order Nearest, Zero, Up, Down
+    const RN: u32 = 0;
+    const RZ: u32 = 1;
+    const RU: u32 = 2;
+    const RD: u32 = 3;
+
+    /// 64-bit FPU in FR = 1 mode.
+    fn cop1(fcsr: u32) -> Cop1 {
+        Cop1 {
+            fcsr,
+            fir: FIR_DEFAULT | FIR_F64 | FIR_L,
+            fr: true,
+            ..Cop1::default()
+        }
+    }
+
+    fn op(c: &mut Cop1, fmt: u8, funct: u8) -> Result<(), Exception> {
+        let ins = Cop1Ins {
+            opcode: 0x11,
+            fmt,
+            ft: 4,
+            fs: 2,
+            fd: 0,
+            funct,
+        };
+        FPU::execute(c, ins)
+    }
+
+    /// fd = fs `funct` ft on doubles; returns (fd, FCSR).
+    fn run_d(fcsr: u32, funct: u8, a: f64, b: f64) -> (f64, u32) {
+        let mut c = cop1(fcsr);
+        c.write_d(2, a);
+        c.write_d(4, b);
+        op(&mut c, FMT_D, funct).unwrap();
+        (c.read_d(0), c.fcsr())
+    }
+
+    fn run_s(fcsr: u32, funct: u8, a: f32, b: f32) -> (f32, u32) {
+        let mut c = cop1(fcsr);
+        c.write_s(2, a);
+        c.write_s(4, b);
+        op(&mut c, FMT_S, funct).unwrap();
+        (c.read_s(0), c.fcsr())
+    }
+
+    /// Same operation under RN, RZ, RU and RD.
+    fn modes_d(funct: u8, a: f64, b: f64) -> [f64; 4] {
+        [RN, RZ, RU, RD].map(|rm| run_d(rm, funct, a, b).0)
+    }
+
+    fn cause(fcsr: u32) -> u32 {
+        (fcsr >> CAUSE_SHIFT) & 0x3F
+    }
+
+    fn flags(fcsr: u32) -> u32 {
+        (fcsr >> FLAGS_SHIFT) & 0x1F
+    }
+
+    #[test]
+    fn add_sub_rounding() {
+        let tiny = 2f64.powi(-60);
+        let up = 1f64.next_up();
+        let down = 1f64.next_down();
+        assert_eq!(modes_d(0x00, 1.0, tiny), [1.0, 1.0, up, 1.0]);
+        assert_eq!(modes_d(0x00, -1.0, -tiny), [-1.0, -1.0, -1.0, -up]);
+        assert_eq!(modes_d(0x01, 1.0, tiny), [1.0, down, 1.0, down]);
+
+        let s = [RN, RZ, RU, RD].map(|rm| run_s(rm, 0x00, 1.0, 2f32.powi(-30)).0);
+        assert_eq!(s, [1.0, 1.0, 1f32.next_up(), 1.0]);
+    }
+
+    #[test]
+    fn mul_div_sqrt_rounding() {
+        let e = 1f64.next_up(); // 1 + 2^-52; e * e = 1 + 2^-51 + 2^-104
+        let p = 1.0 + 2f64.powi(-51);
+        assert_eq!(modes_d(0x02, e, e), [p, p, p.next_up(), p]);
+
+        let third: f64 = 1.0 / 3.0; // below the exact value
+        assert_eq!(
+            modes_d(0x03, 1.0, 3.0),
+            [third, third, third.next_up(), third]
+        );
+
+        let r2 = 2f64.sqrt(); // above the exact value
+        assert_eq!(
+            modes_d(0x04, 2.0, 0.0),
+            [r2, r2.next_down(), r2, r2.next_down()]
+        );
+    }
+
+    #[test]
+    fn conversion_rounding() {
+        // CVT.S.D of 1/3: 0x3EAAAAAB is the nearest single, above the value
+        let s = [RN, RZ, RU, RD].map(|rm| {
+            let mut c = cop1(rm);
+            c.write_d(2, 1.0 / 3.0);
+            op(&mut c, FMT_D, 0x20).unwrap();
+            c.read_w(0)
+        });
+        assert_eq!(s, [0x3EAA_AAAB, 0x3EAA_AAAA, 0x3EAA_AAAB, 0x3EAA_AAAA]);
+
+        // CVT.W.D honours RM; ROUND/TRUNC/CEIL/FLOOR.W ignore it
+        let w = |rm, funct, v| {
+            let mut c = cop1(rm);
+            c.write_d(2, v);
+            op(&mut c, FMT_D, funct).unwrap();
+            c.read_w(0) as i32
+        };
+        assert_eq!([RN, RZ, RU, RD].map(|rm| w(rm, 0x24, 2.5)), [2, 2, 3, 2]);
+        assert_eq!([RN, RZ, RU, RD].map(|rm| w(rm, 0x24, -2.5)), [-2, -2, -2, -3]);
+        assert_eq!([0x0C, 0x0D, 0x0E, 0x0F].map(|f| w(RU, f, -1.5)), [-2, -1, -1, -2]);
+
+        // CVT.S.W of 2^24 + 1 is a tie
+        let s = [RN, RZ, RU, RD].map(|rm| {
+            let mut c = cop1(rm);
+            c.write_w(2, 16_777_217);
+            op(&mut c, FMT_W, 0x20).unwrap();
+            c.read_s(0)
+        });
+        assert_eq!(s, [16_777_216.0, 16_777_216.0, 16_777_218.0, 16_777_216.0]);
+
+        // CVT.D.L of 2^53 + 1 rounds too; CVT.L.D of 2^62 is exact
+        let mut c = cop1(RU);
+        c.write_l(2, (1u64 << 53) + 1);
+        op(&mut c, FMT_L, 0x21).unwrap();
+        assert_eq!(c.read_d(0), 9_007_199_254_740_994.0);
+        c.write_d(2, 2f64.powi(62));
+        op(&mut c, FMT_D, 0x25).unwrap();
+        assert_eq!(c.read_l(0), 1 << 62);
+    }
+
+    #[test]
+    fn overflow_and_underflow() {
+        let max = f64::MAX;
+        assert_eq!(
+            modes_d(0x00, max, max),
+            [f64::INFINITY, max, f64::INFINITY, max]
+        );
+        assert_eq!(
+            modes_d(0x01, -max, max),
+            [f64::NEG_INFINITY, -max, -max, f64::NEG_INFINITY]
+        );
+        let (r, fcsr) = run_d(RU, 0x00, max, 1.0);
+        assert_eq!(r, f64::INFINITY);
+        assert_eq!(cause(fcsr), FPE_OVERFLOW | FPE_INEXACT);
+
+        let (r, fcsr) = run_s(RN, 0x02, 3e38, 10.0);
+        assert_eq!(r, f32::INFINITY);
+        assert_eq!(cause(fcsr), FPE_OVERFLOW | FPE_INEXACT);
+
+        // Tiny and inexact: Underflow; tiny but exact: nothing
+        let b = 0.5 + 2f64.powi(-53);
+        let (_, fcsr) = run_d(RN, 0x02, f64::MIN_POSITIVE, b);
+        assert_eq!(cause(fcsr), FPE_UNDERFLOW | FPE_INEXACT);
+        let (r, fcsr) = run_d(RN, 0x02, f64::MIN_POSITIVE, 0.5);
+        assert_eq!(r, f64::MIN_POSITIVE / 2.0);
+        assert_eq!(cause(fcsr), 0);
+
+        let (_, fcsr) = run_d(RN, 0x03, f64::MIN_POSITIVE, 3.0);
+        assert_eq!(cause(fcsr), FPE_UNDERFLOW | FPE_INEXACT);
+        let (_, fcsr) = run_s(RN, 0x02, f32::MIN_POSITIVE, 0.3);
+        assert_eq!(cause(fcsr), FPE_UNDERFLOW | FPE_INEXACT);
+    }
+
+    #[test]
+    fn inexact_accumulates_in_flags() {
+        let mut c = cop1(RN);
+        c.write_d(2, 1.0);
+        c.write_d(4, 3.0);
+        op(&mut c, FMT_D, 0x03).unwrap();
+        assert_eq!(cause(c.fcsr()), FPE_INEXACT);
+        op(&mut c, FMT_D, 0x00).unwrap(); // 1 + 3, exact
+        assert_eq!(cause(c.fcsr()), 0);
+        assert_eq!(flags(c.fcsr()), FPE_INEXACT);
+    }
+
+    #[test]
+    fn signed_zero_sums() {
+        let is_neg_zero = |v: f64| v == 0.0 && v.is_sign_negative();
+        let z = modes_d(0x01, 1.5, 1.5);
+        assert!(z[..3].iter().all(|v| *v == 0.0 && v.is_sign_positive()));
+        assert!(is_neg_zero(z[3]));
+        assert!(is_neg_zero(run_d(RD, 0x00, 1.5, -1.5).0));
+        assert!(is_neg_zero(run_d(RD, 0x00, 0.0, -0.0).0));
+        assert!(!is_neg_zero(run_d(RD, 0x00, 0.0, 0.0).0));
+        assert!(modes_d(0x00, -0.0, -0.0).into_iter().all(is_neg_zero));
+    }
+
+    #[test]
+    fn enabled_exceptions_trap() {
+        let sentinel = 0x1234_5678_9ABC_DEF0;
+        let cases: [(u8, f64, f64, u32); 4] = [
+            (0x03, 1.0, 3.0, FPE_INEXACT),
+            (0x00, f64::MAX, f64::MAX, FPE_OVERFLOW),
+            (0x03, 1.0, 0.0, FPE_DIV_ZERO),
+            (0x04, -1.0, 0.0, FPE_INVALID),
+        ];
+        for (funct, a, b, exc) in cases {
+            // Flags: Underflow already latched from earlier code
+            let mut c = cop1((exc << ENABLES_SHIFT) | (FPE_UNDERFLOW << FLAGS_SHIFT));
+            c.write_l(0, sentinel);
+            c.write_d(2, a);
+            c.write_d(4, b);
+            let err = op(&mut c, FMT_D, funct).unwrap_err();
+            assert_eq!(err.code, ExceptionCode::FloatingPoint);
+            assert_eq!(c.read_l(0), sentinel);
+            assert_eq!(flags(c.fcsr()), FPE_UNDERFLOW);
+            assert_ne!(cause(c.fcsr()) & exc, 0);
+        }
+    }
+
+    #[test]
+    fn ctc1_with_enabled_cause_traps() {
+        let mut c = cop1(RN);
+        assert!(c.write_control(31, (FPE_INVALID << CAUSE_SHIFT) | FPE_INEXACT << 7).is_ok());
+        let err = c.write_control(31, (FPE_INVALID << CAUSE_SHIFT) | (FPE_INVALID << 7));
+        assert_eq!(err.unwrap_err().code, ExceptionCode::FloatingPoint);
+        // The write still happened
+        assert_eq!(cause(c.fcsr()), FPE_INVALID);
+    }
+
+    #[test]
+    fn madd_is_unfused() {
+        // (1 + 2^-52)^2 - (1 + 2^-51): the product rounds away the 2^-104
+        let mut c = cop1(RN);
+        let e = 1f64.next_up();
+        c.write_d(2, e);
+        c.write_d(4, e);
+        c.write_d(6, 1.0 + 2f64.powi(-51));
+        FPU::execute_madd(&mut c, 0x29, 6, 2, 4, 0).unwrap(); // MSUB.D
+        assert_eq!(c.read_d(0), 0.0);
+        FPU::execute_madd(&mut c, 0x31, 6, 2, 4, 0).unwrap(); // NMADD.D
+        assert_eq!(c.read_d(0), -(2.0 + 2f64.powi(-50)));
+    }
+}
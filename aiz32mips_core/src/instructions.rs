@@ -75,7 +75,13 @@ pub struct Special3 {
 }
 
 impl Instruction {
+    /// Total decode: every word maps to exactly one variant, and encodings
+    /// outside the MIPS32 Release 2 instruction set become `Invalid`.
     pub fn decode(instr: u32) -> Self {
+        if !Self::is_defined(instr) {
+            return Instruction::Invalid(instr);
+        }
+
         let opcode = ((instr >> 26) & 0x3F) as u8;
         match opcode {
             0x00 => Instruction::RType(RType::decode(instr)), // SPECIAL
@@ -88,6 +94,82 @@ impl Instruction {
         }
     }
 
+    /// True when `instr` is a MIPS32 Release 2 opcode/funct/fmt combination
+    /// that the executor implements. Paired-single and MIPS-3D (FIR.PS and
+    /// FIR.3D are clear on every model) are left out. Per-model gating is
+    /// done at execution: Release 2 additions raise RI on R1 cores (Config0.AR),
+    /// the L format, .L conversions, RECIP/RSQRT and LUXC1/SUXC1 raise RI
+    /// without a 64-bit FPU (FIR.F64 / FIR.L), and COP1 needs Status.CU1.
+    pub fn is_defined(instr: u32) -> bool {
+        let opcode = (instr >> 26) & 0x3F;
+        let rs = (instr >> 21) & 0x1F;
+        let rt = (instr >> 16) & 0x1F;
+        let sa = (instr >> 6) & 0x1F;
+        let funct = instr & 0x3F;
+
+        match opcode {
+            // SPECIAL
+            0x00 => matches!(
+                funct,
+                0x00..=0x04
+                    | 0x06..=0x0D
+                    | 0x0F..=0x13
+                    | 0x18..=0x1B
+                    | 0x20..=0x27
+                    | 0x2A
+                    | 0x2B
+                    | 0x30..=0x34
+                    | 0x36
+            ),
+            // REGIMM
+            0x01 => matches!(rt, 0x00..=0x03 | 0x08..=0x0C | 0x0E | 0x10..=0x13 | 0x1F),
+            // COP0: MFC0, MTC0, RDPGPR, MFMC0, WRPGPR and the CO functions
+            0x10 => match rs {
+                0x00 | 0x04 | 0x0A | 0x0B | 0x0E => true,
+                0x10..=0x1F => matches!(funct, 0x01 | 0x02 | 0x06 | 0x08 | 0x18 | 0x1F | 0x20),
+                _ => false,
+            },
+            0x11 => Self::is_defined_cop1(rs, funct),
+            // COP1X: indexed loads/stores, PREFX, MADD/MSUB/NMADD/NMSUB.S/D
+            0x13 => {
+                matches!(funct, 0x00 | 0x01 | 0x05 | 0x08 | 0x09 | 0x0D | 0x0F)
+                    || matches!(funct, 0x20 | 0x21 | 0x28 | 0x29 | 0x30 | 0x31 | 0x38 | 0x39)
+            }
+            // SPECIAL2
+            0x1C => matches!(funct, 0x00..=0x02 | 0x04 | 0x05 | 0x20 | 0x21 | 0x3F),
+            // SPECIAL3
+            0x1F => match funct {
+                0x00 | 0x04 | 0x3B => true,
+                0x20 => matches!(sa, 0x02 | 0x10 | 0x18), // BSHFL
+                _ => false,
+            },
+            // Jumps, branches, immediates, COP2, loads/stores, CACHE, PREF
+            0x02..=0x0F | 0x12 | 0x14..=0x17 => true,
+            0x20..=0x26 | 0x28..=0x2B | 0x2E..=0x33 | 0x35 | 0x36 | 0x38..=0x3A => true,
+            0x3D | 0x3E => true,
+            _ => false,
+        }
+    }
+
+    fn is_defined_cop1(fmt: u32, funct: u32) -> bool {
+        match fmt {
+            // MFC1, CFC1, MFHC1, MTC1, CTC1, MTHC1, BC1
+            0x00 | 0x02 | 0x03 | 0x04 | 0x06 | 0x07 | 0x08 => true,
+            0x10 | 0x11 => {
+                // CVT.S is not defined on S, nor CVT.D on D
+                let cvt_other = if fmt == 0x10 { 0x21 } else { 0x20 };
+                funct == cvt_other
+                    || matches!(
+                        funct,
+                        0x00..=0x0F | 0x11..=0x13 | 0x15 | 0x16 | 0x24 | 0x25 | 0x30..=0x3F
+                    )
+            }
+            // W and L: CVT.S / CVT.D only
+            0x14 | 0x15 => matches!(funct, 0x20 | 0x21),
+            _ => false,
+        }
+    }
+
     pub fn encode(&self) -> u32 {
         match *self {
             Instruction::RType(r) => r.encode(),
@@ -1,7 +1,12 @@
 pub mod alu;
 pub mod cop;
 pub mod cpu;
+pub mod exception;
+pub mod fpu;
 pub mod instructions;
 pub mod memory;
+pub mod model;
 pub mod registers;
+pub mod syscall;
+pub mod tlb;
 pub mod devices;
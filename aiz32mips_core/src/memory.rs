@@ -17,6 +17,14 @@ pub trait Device: Any {
     fn read8(&mut self, paddr: u32) -> MemResult<u8>;
     fn write8(&mut self, paddr: u32, value: u8) -> MemResult<()>;
 
+    /// Register-based devices (MMIO) define their registers as whole
+    /// values: the bus hands them aligned 16/32-bit accesses through
+    /// `read16`/`read32`/`write16`/`write32` instead of splitting them into
+    /// bytes in its own byte order. Plain memory keeps the default `false`.
+    fn is_mmio(&self) -> bool {
+        false
+    }
+
     fn read16(&mut self, paddr: u32) -> MemResult<u16> {
         let lo = self.read8(paddr)? as u16;
         let hi = self.read8(paddr + 1)? as u16;
@@ -61,6 +69,8 @@ pub trait Device: Any {
 pub struct MemoryBus {
     devices: Vec<Box<dyn Device>>,
     pub little_endian: bool,
+    /// Word reserved by LL; any write to it breaks the link.
+    link: Option<u32>,
 }
 
 impl MemoryBus {
@@ -68,6 +78,7 @@ impl MemoryBus {
         Self {
             devices: Vec::new(),
             little_endian,
+            link: None,
         }
     }
 
@@ -82,6 +93,14 @@ impl MemoryBus {
             .map(|b| b.as_mut())
     }
 
+    /// MMIO device for a naturally aligned `size`-byte access, if any.
+    fn mmio_device_mut(&mut self, paddr: u32, size: u32) -> Option<&mut dyn Device> {
+        if paddr & (size - 1) != 0 {
+            return None;
+        }
+        self.find_device_mut(paddr).filter(|d| d.is_mmio())
+    }
+
     pub fn read8(&mut self, paddr: u32) -> MemResult<u8> {
         if let Some(dev) = self.find_device_mut(paddr) {
             dev.read8(paddr)
@@ -91,21 +110,51 @@ impl MemoryBus {
     }
 
     pub fn read16(&mut self, paddr: u32) -> MemResult<u16> {
+        if let Some(dev) = self.mmio_device_mut(paddr, 2) {
+            return dev.read16(paddr);
+        }
         let b = [self.read8(paddr)?, self.read8(paddr + 1)?];
-        Ok(u16::from_le_bytes(b))
+        Ok(if self.little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
     }
 
     pub fn read32(&mut self, paddr: u32) -> MemResult<u32> {
+        if let Some(dev) = self.mmio_device_mut(paddr, 4) {
+            return dev.read32(paddr);
+        }
         let b = [
             self.read8(paddr)?,
             self.read8(paddr + 1)?,
             self.read8(paddr + 2)?,
             self.read8(paddr + 3)?,
         ];
-        Ok(u32::from_le_bytes(b))
+        Ok(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    pub fn set_link(&mut self, paddr: u32) {
+        self.link = Some(paddr & !3);
+    }
+
+    /// True if no write has touched the word reserved at `paddr` since LL.
+    pub fn link_valid(&self, paddr: u32) -> bool {
+        self.link == Some(paddr & !3)
+    }
+
+    pub fn clear_link(&mut self) {
+        self.link = None;
     }
 
     pub fn write8(&mut self, paddr: u32, value: u8) -> MemResult<()> {
+        if self.link == Some(paddr & !3) {
+            self.link = None;
+        }
         if let Some(dev) = self.find_device_mut(paddr) {
             dev.write8(paddr, value)
         } else {
@@ -114,19 +163,41 @@ impl MemoryBus {
     }
 
     pub fn write16(&mut self, paddr: u32, value: u16) -> MemResult<()> {
-        let bytes = value.to_le_bytes();
+        if self.link == Some(paddr & !3) {
+            self.link = None;
+        }
+        if let Some(dev) = self.mmio_device_mut(paddr, 2) {
+            return dev.write16(paddr, value);
+        }
+        let bytes = if self.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        };
         self.write8(paddr, bytes[0])?;
         self.write8(paddr + 1, bytes[1])
     }
 
     pub fn write32(&mut self, paddr: u32, value: u32) -> MemResult<()> {
-        let bytes = value.to_le_bytes();
+        if self.link == Some(paddr & !3) {
+            self.link = None;
+        }
+        if let Some(dev) = self.mmio_device_mut(paddr, 4) {
+            return dev.write32(paddr, value);
+        }
+        let bytes = if self.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        };
         for (i, b) in bytes.iter().enumerate() {
             self.write8(paddr + i as u32, *b)?;
         }
         Ok(())
     }
 
+    /// Fixed-mapping translation for host-side accesses (loaders, display).
+    /// Guest accesses go through the CPU's TLB instead.
     pub fn translate_vaddr(&self, vaddr: u32) -> MemResult<u32> {
         match vaddr {
             0x0000_0000..=0x7FFF_FFFF => Ok(vaddr), // KUSEG
@@ -136,17 +207,30 @@ impl MemoryBus {
         }
     }
 
+    /// Natural alignment check for a `size`-byte access at `vaddr`.
+    pub fn check_alignment(vaddr: u32, size: u32, store: bool) -> MemResult<()> {
+        if vaddr & (size - 1) == 0 {
+            Ok(())
+        } else if store {
+            Err(MemoryError::AddressErrorStore(vaddr))
+        } else {
+            Err(MemoryError::AddressErrorLoad(vaddr))
+        }
+    }
+
     pub fn read8_virt(&mut self, vaddr: u32) -> MemResult<u8> {
         let paddr = self.translate_vaddr(vaddr)?;
         self.read8(paddr)
     }
 
     pub fn read16_virt(&mut self, vaddr: u32) -> MemResult<u16> {
+        Self::check_alignment(vaddr, 2, false)?;
         let paddr = self.translate_vaddr(vaddr)?;
         self.read16(paddr)
     }
 
     pub fn read32_virt(&mut self, vaddr: u32) -> MemResult<u32> {
+        Self::check_alignment(vaddr, 4, false)?;
         let paddr = self.translate_vaddr(vaddr)?;
         self.read32(paddr)
     }
@@ -157,11 +241,13 @@ impl MemoryBus {
     }
 
     pub fn write16_virt(&mut self, vaddr: u32, val: u16) -> MemResult<()> {
+        Self::check_alignment(vaddr, 2, true)?;
         let paddr = self.translate_vaddr(vaddr)?;
         self.write16(paddr, val)
     }
 
     pub fn write32_virt(&mut self, vaddr: u32, val: u32) -> MemResult<()> {
+        Self::check_alignment(vaddr, 4, true)?;
         let paddr = self.translate_vaddr(vaddr)?;
         self.write32(paddr, val)
     }
@@ -0,0 +1,208 @@
+use crate::cop::{
+    CONFIG_M, CONFIG0_BE, CONFIG0_MT_TLB, CONFIG1_FP, CONFIG1_MMU_SHIFT, CONFIG3_ULRI,
+    FIR_DEFAULT, FIR_F64, FIR_L,
+};
+
+/// MIPS32 architecture revision (Config0.AR).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IsaRevision {
+    R1 = 0,
+    R2 = 1,
+}
+
+/// Geometry of one L1 cache, as reported in Config1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheGeometry {
+    /// Sets per way (32 to 4096, power of two).
+    pub sets: u32,
+    /// Line size in bytes (4 to 128, power of two).
+    pub line_bytes: u32,
+    /// Associativity (1 to 8 ways).
+    pub ways: u32,
+}
+
+impl CacheGeometry {
+    /// Config1 S/L/A triplet (sets, line, associativity), 9 bits.
+    fn encode(cache: Option<CacheGeometry>) -> u32 {
+        let Some(c) = cache else {
+            return 0; // L = 0: no cache
+        };
+        let s = if c.sets == 32 { 7 } else { (c.sets / 64).trailing_zeros() };
+        let l = (c.line_bytes / 2).trailing_zeros();
+        let a = c.ways - 1;
+        (s << 6) | (l << 3) | a
+    }
+
+    pub fn size(&self) -> u32 {
+        self.sets * self.line_bytes * self.ways
+    }
+}
+
+/// Static description of the emulated core: what guest firmware sees in
+/// PRId/Config0-3 and which optional instructions are accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CpuModel {
+    pub name: &'static str,
+    pub prid: u32,
+    pub isa: IsaRevision,
+    pub fpu: bool,
+    /// JTLB size, 1 to 64 (Config1.MMUSize + 1). Private so that only
+    /// `with_tlb_entries` can change it.
+    tlb_entries: u32,
+    pub icache: Option<CacheGeometry>,
+    pub dcache: Option<CacheGeometry>,
+    pub little_endian: bool,
+}
+
+const CACHE_16K_4WAY: CacheGeometry = CacheGeometry {
+    sets: 256,
+    line_bytes: 16,
+    ways: 4,
+};
+
+const CACHE_32K_4WAY: CacheGeometry = CacheGeometry {
+    sets: 256,
+    line_bytes: 32,
+    ways: 4,
+};
+
+impl CpuModel {
+    /// MIPS32 R1, no FPU.
+    pub const M4KC: CpuModel = CpuModel {
+        name: "4kc",
+        prid: 0x0001_8000,
+        isa: IsaRevision::R1,
+        fpu: false,
+        tlb_entries: 16,
+        icache: Some(CACHE_16K_4WAY),
+        dcache: Some(CACHE_16K_4WAY),
+        little_endian: true,
+    };
+
+    /// MIPS32 R2, no FPU.
+    pub const M4KEC: CpuModel = CpuModel {
+        name: "4kec",
+        prid: 0x0001_9000,
+        isa: IsaRevision::R2,
+        fpu: false,
+        tlb_entries: 16,
+        icache: Some(CACHE_16K_4WAY),
+        dcache: Some(CACHE_16K_4WAY),
+        little_endian: true,
+    };
+
+    /// MIPS32 R2 with FPU (default).
+    pub const M24KF: CpuModel = CpuModel {
+        name: "24kf",
+        prid: 0x0001_9300,
+        isa: IsaRevision::R2,
+        fpu: true,
+        tlb_entries: 32,
+        icache: Some(CACHE_32K_4WAY),
+        dcache: Some(CACHE_32K_4WAY),
+        little_endian: true,
+    };
+
+    pub const ALL: [CpuModel; 3] = [Self::M4KC, Self::M4KEC, Self::M24KF];
+
+    /// Largest JTLB Config1.MMUSize can describe.
+    pub const MAX_TLB_ENTRIES: u32 = 64;
+
+    /// Looks up a preset by name (case-insensitive).
+    pub fn by_name(name: &str) -> Option<CpuModel> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name.eq_ignore_ascii_case(name))
+    }
+
+    #[inline]
+    pub fn tlb_entries(&self) -> u32 {
+        self.tlb_entries
+    }
+
+    /// Same model with `entries` JTLB entries, or None outside 1-64.
+    pub fn with_tlb_entries(self, entries: u32) -> Option<CpuModel> {
+        if (1..=Self::MAX_TLB_ENTRIES).contains(&entries) {
+            Some(CpuModel {
+                tlb_entries: entries,
+                ..self
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn config0(&self) -> u32 {
+        let mut c = CONFIG_M | ((self.isa as u32) << 10) | CONFIG0_MT_TLB | 2; // K0 uncached
+        if !self.little_endian {
+            c |= CONFIG0_BE;
+        }
+        c
+    }
+
+    pub fn config1(&self) -> u32 {
+        let mut c = CONFIG_M | ((self.tlb_entries - 1) << CONFIG1_MMU_SHIFT);
+        c |= CacheGeometry::encode(self.icache) << 16;
+        c |= CacheGeometry::encode(self.dcache) << 7;
+        if self.fpu {
+            c |= CONFIG1_FP;
+        }
+        c
+    }
+
+    pub fn config2(&self) -> u32 {
+        CONFIG_M // Config3 present, no L2/L3
+    }
+
+    pub fn config3(&self) -> u32 {
+        // R2 cores implement UserLocal for RDHWR $29
+        if self.isa >= IsaRevision::R2 { CONFIG3_ULRI } else { 0 }
+    }
+
+    /// FPU implementation register (CP1 register 0). R2 FPUs are 64-bit
+    /// (Status.FR usable) and implement the L format.
+    pub fn fir(&self) -> u32 {
+        match (self.fpu, self.isa) {
+            (false, _) => 0,
+            (true, IsaRevision::R1) => FIR_DEFAULT,
+            (true, IsaRevision::R2) => FIR_DEFAULT | FIR_F64 | FIR_L,
+        }
+    }
+}
+
+impl Default for CpuModel {
+    fn default() -> Self {
+        Self::M24KF
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tlb_entries_are_checked() {
+        let m = CpuModel::M4KC;
+        assert_eq!(m.with_tlb_entries(0), None);
+        assert_eq!(m.with_tlb_entries(65), None);
+        let one = m.with_tlb_entries(1).unwrap();
+        assert_eq!((one.config1() >> CONFIG1_MMU_SHIFT) & 0x3F, 0);
+        let full = m.with_tlb_entries(64).unwrap();
+        assert_eq!(full.tlb_entries(), 64);
+        assert_eq!((full.config1() >> CONFIG1_MMU_SHIFT) & 0x3F, 63);
+        assert_eq!(full.prid, m.prid);
+    }
+
+    #[test]
+    fn fir_follows_fpu_and_revision() {
+        assert_eq!(CpuModel::M4KC.fir(), 0);
+        assert_eq!(CpuModel::M4KEC.fir(), 0);
+        let fir = CpuModel::M24KF.fir();
+        assert_eq!(fir, FIR_DEFAULT | FIR_F64 | FIR_L);
+        let r1 = CpuModel {
+            isa: IsaRevision::R1,
+            ..CpuModel::M24KF
+        };
+        assert_eq!(r1.fir() & (FIR_F64 | FIR_L), 0);
+    }
+}
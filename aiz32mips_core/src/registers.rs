@@ -9,17 +9,6 @@ pub struct SpecialRegisters {
     pub hi: u32,
     pub lo: u32,
     pub pc: u32,
-    pub epc: u32,
-    pub status: u32,
-    pub cause: u32,
-    pub badvaddr: u32,
-    pub index: u32,
-    pub random: u32,
-    pub entrylo0: u32,
-    pub entrylo1: u32,
-    pub count: u32,
-    pub compare: u32,
-    pub entryhi: u32,
 }
 
 impl Default for Registers {
@@ -37,17 +26,6 @@ impl Default for SpecialRegisters {
             hi: 0,
             lo: 0,
             pc: 0xBFC00000,
-            epc: 0,
-            status: 0x00000000,
-            cause: 0,
-            badvaddr: 0,
-            index: 0,
-            random: 31,
-            entrylo0: 0,
-            entrylo1: 0,
-            count: 0,
-            compare: 0,
-            entryhi: 0,
         }
     }
 }
@@ -108,17 +86,6 @@ impl Registers {
             SpecialReg::Hi => self.special.hi,
             SpecialReg::Lo => self.special.lo,
             SpecialReg::Pc => self.special.pc,
-            SpecialReg::Epc => self.special.epc,
-            SpecialReg::Status => self.special.status,
-            SpecialReg::Cause => self.special.cause,
-            SpecialReg::BadVAddr => self.special.badvaddr,
-            SpecialReg::Index => self.special.index,
-            SpecialReg::Random => self.special.random,
-            SpecialReg::EntryLo0 => self.special.entrylo0,
-            SpecialReg::EntryLo1 => self.special.entrylo1,
-            SpecialReg::Count => self.special.count,
-            SpecialReg::Compare => self.special.compare,
-            SpecialReg::EntryHi => self.special.entryhi,
         }
     }
 
@@ -127,24 +94,9 @@ impl Registers {
             SpecialReg::Hi => self.special.hi = value,
             SpecialReg::Lo => self.special.lo = value,
             SpecialReg::Pc => self.special.pc = value,
-            SpecialReg::Epc => self.special.epc = value,
-            SpecialReg::Status => self.special.status = value,
-            SpecialReg::Cause => self.special.cause = value,
-            SpecialReg::BadVAddr => self.special.badvaddr = value,
-            SpecialReg::Index => self.special.index = value,
-            SpecialReg::Random => self.special.random = value,
-            SpecialReg::EntryLo0 => self.special.entrylo0 = value,
-            SpecialReg::EntryLo1 => self.special.entrylo1 = value,
-            SpecialReg::Count => self.special.count = value,
-            SpecialReg::Compare => self.special.compare = value,
-            SpecialReg::EntryHi => self.special.entryhi = value,
         }
     }
 
-    pub fn tick(&mut self) {
-        self.special.count = self.special.count.wrapping_add(1);
-    }
-
     pub fn dump(&self) {
         println!("--- CPU Registers ---");
         for (i, reg) in self.general.iter().enumerate() {
@@ -153,9 +105,6 @@ impl Registers {
         println!("HI = 0x{:08X}", self.special.hi);
         println!("LO = 0x{:08X}", self.special.lo);
         println!("PC = 0x{:08X}", self.special.pc);
-        println!("EPC = 0x{:08X}", self.special.epc);
-        println!("Status = 0x{:08X}", self.special.status);
-        println!("Cause  = 0x{:08X}", self.special.cause);
     }
 }
 
@@ -164,15 +113,4 @@ pub enum SpecialReg {
     Hi,
     Lo,
     Pc,
-    Epc,
-    Status,
-    Cause,
-    BadVAddr,
-    Index,
-    Random,
-    EntryLo0,
-    EntryLo1,
-    Count,
-    Compare,
-    EntryHi,
 }
@@ -0,0 +1,25 @@
+use crate::memory::MemoryBus;
+use crate::registers::Registers;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HostTrap {
+    Syscall,
+    Break,
+    /// SDBBP, offered to the host debugger before entering debug mode.
+    Sdbbp,
+}
+
+/// Host-side hook consulted by SYSCALL, BREAK and SDBBP before the guest
+/// exception is raised (semihosting, test harness exit codes, debuggers...).
+pub trait SyscallHandler {
+    /// `code` is the 20-bit field of the instruction. Return true to consume
+    /// the trap: execution continues at the next instruction and no
+    /// exception is taken.
+    fn handle(
+        &mut self,
+        kind: HostTrap,
+        code: u32,
+        regs: &mut Registers,
+        bus: &mut MemoryBus,
+    ) -> bool;
+}
@@ -0,0 +1,209 @@
+// EntryLo fields
+pub const ENTRYLO_G: u32 = 1 << 0;
+pub const ENTRYLO_V: u32 = 1 << 1;
+pub const ENTRYLO_D: u32 = 1 << 2;
+pub const ENTRYLO_PFN_SHIFT: u32 = 6;
+pub const ENTRYLO_WRITE_MASK: u32 = 0x3FFF_FFFF;
+
+// EntryHi / PageMask / Index fields
+pub const ENTRYHI_VPN2_MASK: u32 = 0xFFFF_E000;
+pub const ENTRYHI_ASID_MASK: u32 = 0xFF;
+pub const PAGEMASK_MASK: u32 = 0x1FFF_E000;
+pub const INDEX_P: u32 = 1 << 31;
+
+/// One JTLB entry: a pair of pages (even/odd) sharing a VPN2.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TlbEntry {
+    pub page_mask: u32,
+    /// EntryHi as written: VPN2 and ASID.
+    pub entry_hi: u32,
+    /// EntryLo0/1 without the G bit, which is kept per entry.
+    pub lo: [u32; 2],
+    pub global: bool,
+}
+
+impl TlbEntry {
+    #[inline]
+    pub fn asid(&self) -> u8 {
+        (self.entry_hi & ENTRYHI_ASID_MASK) as u8
+    }
+
+    /// Address bits compared against VPN2 for this page size.
+    #[inline]
+    fn vpn2_mask(&self) -> u32 {
+        ENTRYHI_VPN2_MASK & !self.page_mask
+    }
+
+    fn matches(&self, vaddr: u32, asid: u8) -> bool {
+        let mask = self.vpn2_mask();
+        (vaddr & mask) == (self.entry_hi & mask) && (self.global || self.asid() == asid)
+    }
+}
+
+/// Why a mapped access could not be translated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlbFault {
+    /// No entry matches (serviced by the refill handler).
+    Refill,
+    /// Matching entry with V clear.
+    Invalid,
+    /// Store to a matching entry with D clear.
+    Modified,
+}
+
+/// MIPS32 joint TLB (16 or 32 entries).
+pub struct Tlb {
+    pub entries: Vec<TlbEntry>,
+}
+
+impl Tlb {
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![TlbEntry::default(); size],
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn read(&self, index: usize) -> TlbEntry {
+        self.entries[index % self.len()]
+    }
+
+    pub fn write(&mut self, index: usize, entry: TlbEntry) {
+        let n = self.len();
+        self.entries[index % n] = entry;
+    }
+
+    /// TLBP: index of the entry matching `entry_hi` (VPN2 and ASID).
+    pub fn probe(&self, entry_hi: u32) -> Option<usize> {
+        let asid = (entry_hi & ENTRYHI_ASID_MASK) as u8;
+        self.entries.iter().position(|e| e.matches(entry_hi, asid))
+    }
+
+    /// Maps `vaddr` through the TLB for the address space `asid`.
+    pub fn translate(&self, vaddr: u32, asid: u8, store: bool) -> Result<u32, TlbFault> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.matches(vaddr, asid))
+            .ok_or(TlbFault::Refill)?;
+
+        // Page offset mask; the bit above it selects the even/odd page
+        let offset_mask = (entry.page_mask | 0x1FFF) >> 1;
+        let odd = (vaddr & (offset_mask + 1)) != 0;
+        let lo = entry.lo[odd as usize];
+
+        if lo & ENTRYLO_V == 0 {
+            return Err(TlbFault::Invalid);
+        }
+        if store && lo & ENTRYLO_D == 0 {
+            return Err(TlbFault::Modified);
+        }
+
+        let frame = ((lo & ENTRYLO_WRITE_MASK) >> ENTRYLO_PFN_SHIFT) << 12;
+        Ok((frame & !offset_mask) | (vaddr & offset_mask))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// EntryLo (without G) mapping physical frame `pfn`.
+    fn lo(pfn: u32, valid: bool, dirty: bool) -> u32 {
+        let mut v = pfn << ENTRYLO_PFN_SHIFT;
+        if valid {
+            v |= ENTRYLO_V;
+        }
+        if dirty {
+            v |= ENTRYLO_D;
+        }
+        v
+    }
+
+    fn tlb_with(entry: TlbEntry) -> Tlb {
+        let mut tlb = Tlb::new(16);
+        tlb.write(3, entry);
+        tlb
+    }
+
+    #[test]
+    fn empty_tlb_refills() {
+        let tlb = Tlb::new(16);
+        assert_eq!(tlb.translate(0x0040_0000, 0, false), Err(TlbFault::Refill));
+        assert_eq!(tlb.probe(0x0040_0000), None);
+    }
+
+    #[test]
+    fn even_and_odd_pages() {
+        let tlb = tlb_with(TlbEntry {
+            page_mask: 0,
+            entry_hi: 0x0040_0000 | 5,
+            lo: [lo(0x10, true, true), lo(0x20, true, true)],
+            global: false,
+        });
+        assert_eq!(tlb.translate(0x0040_0123, 5, false), Ok(0x0001_0123));
+        assert_eq!(tlb.translate(0x0040_1FFC, 5, true), Ok(0x0002_0FFC));
+        assert_eq!(tlb.translate(0x0040_2000, 5, false), Err(TlbFault::Refill));
+        assert_eq!(tlb.probe(0x0040_0000 | 5), Some(3));
+    }
+
+    #[test]
+    fn invalid_and_modified() {
+        let tlb = tlb_with(TlbEntry {
+            page_mask: 0,
+            entry_hi: 0x0040_0000,
+            lo: [lo(0x10, true, false), lo(0x20, false, true)],
+            global: true,
+        });
+        assert_eq!(tlb.translate(0x0040_0010, 0, false), Ok(0x0001_0010));
+        assert_eq!(tlb.translate(0x0040_0010, 0, true), Err(TlbFault::Modified));
+        assert_eq!(tlb.translate(0x0040_1010, 0, false), Err(TlbFault::Invalid));
+        // V is checked before D
+        assert_eq!(tlb.translate(0x0040_1010, 0, true), Err(TlbFault::Invalid));
+    }
+
+    #[test]
+    fn page_mask_widens_pages() {
+        // 16 KiB pages: bit 14 selects even/odd, bits 13:0 are offset
+        let tlb = tlb_with(TlbEntry {
+            page_mask: 0x0000_6000,
+            entry_hi: 0x0080_0000,
+            lo: [lo(0x100, true, true), lo(0x104, true, true)],
+            global: true,
+        });
+        assert_eq!(tlb.translate(0x0080_3FFC, 0, false), Ok(0x0010_3FFC));
+        assert_eq!(tlb.translate(0x0080_4000, 0, false), Ok(0x0010_4000));
+        assert_eq!(tlb.translate(0x0080_7FFC, 0, false), Ok(0x0010_7FFC));
+        assert_eq!(tlb.translate(0x0080_8000, 0, false), Err(TlbFault::Refill));
+    }
+
+    #[test]
+    fn asid_and_global() {
+        let private = TlbEntry {
+            page_mask: 0,
+            entry_hi: 0x0040_0000 | 7,
+            lo: [lo(0x10, true, true), 0],
+            global: false,
+        };
+        let tlb = tlb_with(private);
+        assert!(tlb.translate(0x0040_0000, 7, false).is_ok());
+        assert_eq!(tlb.translate(0x0040_0000, 8, false), Err(TlbFault::Refill));
+        assert_eq!(tlb.probe(0x0040_0000 | 8), None);
+
+        let tlb = tlb_with(TlbEntry {
+            global: true,
+            ..private
+        });
+        assert!(tlb.translate(0x0040_0000, 8, false).is_ok());
+        assert_eq!(tlb.probe(0x0040_0000 | 8), Some(3));
+    }
+}
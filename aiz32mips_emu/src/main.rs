@@ -7,19 +7,42 @@ use std::process;
 
 use aiz32mips_core::cpu::CPU;
 use aiz32mips_core::devices::gpu::GpuMmio;
+use aiz32mips_core::devices::intc::InterruptController;
 use aiz32mips_core::devices::vram::GpuVram;
 use aiz32mips_core::devices::{ram::Ram, rom::Rom};
 use aiz32mips_core::memory::MemoryBus;
+use aiz32mips_core::model::CpuModel;
 
 use mmio_offsets::*;
 use ui::display::SdlDisplay;
 
 fn main() -> anyhow::Result<()> {
     // === args ===
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = Vec::new();
+    let mut model = CpuModel::default();
+    let mut argv = env::args();
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--model" => {
+                let name = argv.next().unwrap_or_default();
+                model = CpuModel::by_name(&name).unwrap_or_else(|| {
+                    let names: Vec<_> = CpuModel::ALL.iter().map(|m| m.name).collect();
+                    eprintln!(
+                        "Modelo de CPU desconocido '{}' (opciones: {})",
+                        name,
+                        names.join(", ")
+                    );
+                    process::exit(1);
+                });
+            }
+            "--big-endian" => model.little_endian = false,
+            _ => args.push(arg),
+        }
+    }
     if args.len() < 4 {
         eprintln!(
-            "Uso: {} <rom_path.bin> <font_rom.bin> <ciclos|inf>",
+            "Uso: {} [--model 4kc|4kec|24kf] [--big-endian] \
+             <rom_path.bin> <font_rom.bin> <ciclos|inf>",
             args[0]
         );
         process::exit(1);
@@ -36,10 +59,15 @@ fn main() -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("Error al leer ROM de fuentes '{}': {}", font_rom_path, e))?;
 
     // === bus ===
-    let mut bus = MemoryBus::new(true); // little-endian
+    let mut bus = MemoryBus::new(model.little_endian);
     bus.add_device(Box::new(Ram::new(0x0000_0000, 0x0020_0000))); // 2MB
     bus.add_device(Box::new(Rom::new(0x1FC0_0000, rom_data))); // BIOS
 
+    // === interrupciones ===
+    let intc = InterruptController::new(INTC_MMIO_BASE);
+    let irq_lines = intc.lines();
+    bus.add_device(Box::new(intc));
+
     // === GPU ===
     let vram_base = 0x1000_0000;
     let vram_size = 4 * 1024 * 1024; // 4MB
@@ -64,7 +92,9 @@ fn main() -> anyhow::Result<()> {
     write32(&mut bus, REG_FONTADDR, font_addr as u32);
 
     // === cpu ===
-    let mut cpu = CPU::new();
+    let mut cpu = CPU::with_model(model);
+    println!("[AIZ32] Modelo de CPU: {} (PRId={:#010X})", model.name, model.prid);
+    cpu.connect_irq(irq_lines);
 
     // === sdl ===
     let mut sdl = SdlDisplay::new(3)?; // escala x3
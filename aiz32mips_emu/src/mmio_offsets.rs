@@ -1,3 +1,4 @@
+pub const INTC_MMIO_BASE: u32 = 0x1F80_1000; // controlador de interrupciones
 pub const GPU_MMIO_BASE: u32 = 0x1F80_2000;
 
 pub const REG_WIDTH: u32 = GPU_MMIO_BASE + 0x00; // u16